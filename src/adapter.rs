@@ -1,5 +1,6 @@
 use anyhow::Result;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct ThemeError {
@@ -15,6 +16,103 @@ impl std::fmt::Display for ThemeError {
 
 impl std::error::Error for ThemeError {}
 
+/// Strategy an adapter uses to apply a theme, selected per app with `apps.<key>.method`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApplyMethod {
+    /// Let the adapter pick the best strategy available
+    Auto,
+    /// Edit the app's config file on disk
+    File,
+    /// Talk to a running instance over a socket or similar channel
+    Ipc,
+    /// Invoke the app's own command-line tool
+    Command,
+}
+
+impl std::fmt::Display for ApplyMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ApplyMethod::Auto => "auto",
+            ApplyMethod::File => "file",
+            ApplyMethod::Ipc => "ipc",
+            ApplyMethod::Command => "command",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for ApplyMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ApplyMethod::Auto),
+            "file" => Ok(ApplyMethod::File),
+            "ipc" => Ok(ApplyMethod::Ipc),
+            "command" => Ok(ApplyMethod::Command),
+            other => Err(anyhow::anyhow!(
+                "Unknown method '{}' (expected auto, file, ipc or command)",
+                other
+            )),
+        }
+    }
+}
+
+/// A theme that follows the OS appearance, written as `light:NAME,dark:NAME`
+///
+/// This is Ghostty's native syntax; other adapters that support light/dark variants
+/// accept the same notation in theme mappings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemePair {
+    pub light: String,
+    pub dark: String,
+}
+
+impl ThemePair {
+    /// Parses `light:X,dark:Y` in either order; returns `None` for a plain theme name
+    pub fn parse(value: &str) -> Option<Self> {
+        let (mut light, mut dark) = (None, None);
+        for part in value.split(',') {
+            match part.trim().split_once(':') {
+                Some(("light", name)) if !name.trim().is_empty() => light = Some(name.trim()),
+                Some(("dark", name)) if !name.trim().is_empty() => dark = Some(name.trim()),
+                _ => return None,
+            }
+        }
+
+        Some(Self {
+            light: light?.to_string(),
+            dark: dark?.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for ThemePair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "light:{},dark:{}", self.light, self.dark)
+    }
+}
+
+/// Builds the error for a `method` that `adapter` does not list in `supported_methods`
+pub fn unsupported_method<A: ThemeAdapter + ?Sized>(
+    adapter: &A,
+    method: ApplyMethod,
+) -> ThemeError {
+    let supported: Vec<String> = adapter
+        .supported_methods()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    ThemeError {
+        message: format!(
+            "Method '{}' is not supported (use auto or {})",
+            method,
+            supported.join(", ")
+        ),
+        app_name: adapter.config_key().to_string(),
+    }
+}
+
 #[async_trait::async_trait]
 pub trait ThemeAdapter: Send + Sync {
     async fn set_theme(&self, theme: &str) -> Result<(), ThemeError>;
@@ -28,4 +126,52 @@ pub trait ThemeAdapter: Send + Sync {
     /// Returns default theme mappings for this adapter
     /// Maps global theme names to app-specific theme names
     fn default_themes(&self) -> HashMap<String, String>;
+
+    /// Returns the strategies this adapter can apply themes with, best first
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::File]
+    }
+
+    /// Applies `theme` with a specific strategy
+    ///
+    /// The default forwards to `set_theme` for `Auto` and any supported method, so only
+    /// adapters offering more than one strategy need to override it.
+    async fn set_theme_with(&self, theme: &str, method: ApplyMethod) -> Result<(), ThemeError> {
+        if method == ApplyMethod::Auto || self.supported_methods().contains(&method) {
+            return self.set_theme(theme).await;
+        }
+
+        Err(unsupported_method(self, method))
+    }
+
+    /// Returns the config files `set_theme` writes, so they can be backed up first
+    fn config_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Captures theme state kept outside of `config_files`, e.g. in a settings database
+    ///
+    /// Atomic rollbacks restore it before copying the config files back. `None` means
+    /// the adapter has no such state; one without config files either is then restored
+    /// by re-applying the theme `get_theme` reports.
+    async fn capture_state(&self) -> Result<Option<String>, ThemeError> {
+        Ok(None)
+    }
+
+    /// Puts back state returned by `capture_state`
+    async fn restore_state(&self, _state: &str) -> Result<(), ThemeError> {
+        Ok(())
+    }
+
+    /// Whether running instances only pick up a new theme after they are restarted
+    fn needs_restart(&self) -> bool {
+        false
+    }
+
+    /// Returns setup problems themesync can't fix itself, each with a hint for the user
+    ///
+    /// Shown by `themesync doctor`; adapters without manual setup steps keep the default.
+    async fn doctor(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
@@ -0,0 +1,242 @@
+//! Adapter for Alacritty theme synchronization via TOML `import` entries.
+//!
+//! Themes are TOML files in a themes directory (by default a clone of
+//! `alacritty-theme`); switching replaces the import that points into that directory.
+//!
+//! # Configuration Locations
+//! - macOS: `~/.config/alacritty/alacritty.toml`
+//! - Linux: `~/.config/alacritty/alacritty.toml`
+//!
+//! # Default Themes
+//! - dark: tokyo_night
+//! - light: github_light
+
+use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::config::expand_home;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use toml_edit::{Array, DocumentMut, Item, Table};
+
+pub struct AlacrittyAdapter {
+    config_path: PathBuf,
+    themes_dir: PathBuf,
+}
+
+impl Default for AlacrittyAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlacrittyAdapter {
+    pub fn new() -> Self {
+        Self::with_path(Self::config_dir().join("alacritty.toml"))
+    }
+
+    /// Creates an adapter that rewrites the theme import of the `alacritty.toml` at `config_path`
+    pub fn with_path(config_path: PathBuf) -> Self {
+        Self {
+            config_path,
+            themes_dir: Self::config_dir().join("themes").join("themes"),
+        }
+    }
+
+    /// Looks for `<theme>.toml` files in `themes_dir` instead of the alacritty-theme clone
+    pub fn with_themes_dir(self, themes_dir: PathBuf) -> Self {
+        Self { themes_dir, ..self }
+    }
+
+    // Alacritty reads $XDG_CONFIG_HOME (~/.config) on macOS too, not ~/Library
+    fn config_dir() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config")
+            .join("alacritty")
+    }
+
+    fn read_config(&self) -> Result<String, ThemeError> {
+        if !self.config_path.exists() {
+            return Ok(String::new());
+        }
+
+        std::fs::read_to_string(&self.config_path).map_err(|e| ThemeError {
+            message: format!("Failed to read alacritty.toml: {}", e),
+            app_name: "alacritty".to_string(),
+        })
+    }
+
+    fn write_config(&self, contents: &str) -> Result<(), ThemeError> {
+        write_atomic(&self.config_path, contents).map_err(|e| ThemeError {
+            message: format!("Failed to write alacritty.toml: {}", e),
+            app_name: "alacritty".to_string(),
+        })
+    }
+
+    fn parse_document(config: &str) -> Result<DocumentMut, ThemeError> {
+        config.parse::<DocumentMut>().map_err(|e| ThemeError {
+            message: format!("Failed to parse alacritty.toml: {}", e),
+            app_name: "alacritty".to_string(),
+        })
+    }
+
+    fn is_theme_import(&self, entry: &str) -> bool {
+        expand_home(Path::new(entry)).parent() == Some(self.themes_dir.as_path())
+    }
+
+    /// Formats a theme file path for an import entry, using `~/` for paths under home
+    fn import_entry(theme_file: &Path) -> String {
+        let relative = dirs::home_dir().and_then(|home| theme_file.strip_prefix(home).ok());
+        match relative {
+            Some(rest) => format!("~/{}", rest.display()),
+            None => theme_file.display().to_string(),
+        }
+    }
+
+    /// Points the theme import in `config` at `theme_file`
+    ///
+    /// Edits the legacy top-level `import` when that is what the config uses, and
+    /// `general.import` otherwise. An existing import into the themes directory is
+    /// replaced in place; otherwise the theme is appended so it overrides earlier imports.
+    pub fn update_theme_in_config(
+        &self,
+        config: &str,
+        theme_file: &Path,
+    ) -> Result<String, ThemeError> {
+        let mut document = Self::parse_document(config)?;
+        let entry = Self::import_entry(theme_file);
+
+        let has_general_import = document
+            .get("general")
+            .and_then(|general| general.get("import"))
+            .is_some();
+        let uses_legacy = document.contains_key("import") && !has_general_import;
+        let imports = if uses_legacy {
+            document
+                .entry("import")
+                .or_insert(toml_edit::value(Array::new()))
+        } else {
+            let general = document
+                .entry("general")
+                .or_insert(Item::Table(Table::new()))
+                .as_table_like_mut()
+                .ok_or_else(|| ThemeError {
+                    message: "`general` in alacritty.toml is not a table".to_string(),
+                    app_name: "alacritty".to_string(),
+                })?;
+            general
+                .entry("import")
+                .or_insert(toml_edit::value(Array::new()))
+        };
+
+        let imports = imports.as_array_mut().ok_or_else(|| ThemeError {
+            message: "`import` in alacritty.toml is not an array".to_string(),
+            app_name: "alacritty".to_string(),
+        })?;
+
+        let paths: Vec<_> = imports.iter().map(|value| value.as_str()).collect();
+        let existing = paths
+            .iter()
+            .rposition(|path| path.is_some_and(|path| self.is_theme_import(path)));
+        match existing {
+            Some(index) => {
+                imports.replace(index, entry);
+            }
+            None => imports.push(entry),
+        }
+
+        Ok(document.to_string())
+    }
+
+    /// Returns the name of the last imported theme file from the themes directory
+    pub fn extract_theme_from_config(&self, config: &str) -> Result<Option<String>, ThemeError> {
+        let document = Self::parse_document(config)?;
+        let imports = document
+            .get("general")
+            .and_then(|general| general.get("import"))
+            .or_else(|| document.get("import"))
+            .and_then(Item::as_array);
+
+        let Some(imports) = imports else {
+            return Ok(None);
+        };
+        let paths: Vec<_> = imports.iter().filter_map(|value| value.as_str()).collect();
+        Ok(paths
+            .into_iter()
+            .rfind(|path| self.is_theme_import(path))
+            .and_then(|path| Path::new(path).file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned()))
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for AlacrittyAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let theme_file = self.themes_dir.join(format!("{}.toml", theme));
+        if !theme_file.is_file() {
+            return Err(ThemeError {
+                message: format!("Theme file {} not found", theme_file.display()),
+                app_name: "alacritty".to_string(),
+            });
+        }
+
+        let config = self.read_config()?;
+        let updated = self.update_theme_in_config(&config, &theme_file)?;
+        self.write_config(&updated)?;
+        Ok(())
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let config = self.read_config()?;
+
+        match self.extract_theme_from_config(&config)? {
+            Some(theme) => Ok(theme),
+            None => Ok("default".to_string()), // Alacritty built-in colors
+        }
+    }
+
+    async fn is_available(&self) -> bool {
+        let alacritty_paths = [
+            "/Applications/Alacritty.app/Contents/MacOS/alacritty",
+            "/usr/local/bin/alacritty",
+            "/usr/bin/alacritty",
+            "/opt/homebrew/bin/alacritty",
+        ];
+
+        if alacritty_paths
+            .iter()
+            .any(|path| PathBuf::from(path).exists())
+        {
+            return true;
+        }
+
+        Command::new("alacritty")
+            .arg("--version")
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    fn app_name(&self) -> &str {
+        "Alacritty"
+    }
+
+    fn config_key(&self) -> &str {
+        "alacritty"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "tokyo_night".to_string());
+        themes.insert("light".to_string(), "github_light".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.config_path.clone()]
+    }
+}
@@ -0,0 +1,264 @@
+//! Adapter for Emacs theme synchronization via a generated Elisp file and emacsclient.
+//!
+//! themesync owns `themesync-theme.el`, which disables the enabled themes and loads the
+//! chosen one; the user loads it once from `init.el`. A running server is switched
+//! immediately with `emacsclient --eval`.
+//!
+//! # Configuration Locations
+//! - macOS: `~/.config/emacs/themesync-theme.el` (or `~/.emacs.d/` if that is in use)
+//! - Linux: `~/.config/emacs/themesync-theme.el` (or `~/.emacs.d/` if that is in use)
+//!
+//! # Default Themes
+//! - dark: modus-vivendi
+//! - light: modus-operandi
+
+use crate::adapter::{unsupported_method, ApplyMethod, ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::runner::{CommandRunner, SystemRunner};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const FILE_STEM: &str = "themesync-theme";
+
+pub struct EmacsAdapter {
+    theme_file: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for EmacsAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmacsAdapter {
+    pub fn new() -> Self {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        // Emacs prefers ~/.emacs.d when it exists, then the XDG location
+        let legacy_dir = home.join(".emacs.d");
+        let init_dir = if legacy_dir.is_dir() {
+            legacy_dir
+        } else {
+            home.join(".config").join("emacs")
+        };
+
+        Self::with_path(init_dir.join(format!("{}.el", FILE_STEM)))
+    }
+
+    /// Creates an adapter that writes the generated `themesync-theme.el` to `theme_file`
+    pub fn with_path(theme_file: PathBuf) -> Self {
+        Self {
+            theme_file,
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Switches the running server with `emacsclient --eval` through `runner`
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    /// Checks that `theme` is a plain symbol, since it is spliced into Elisp
+    fn validate_theme(theme: &str) -> Result<(), ThemeError> {
+        let is_symbol = !theme.is_empty()
+            && theme
+                .chars()
+                .all(|c| c.is_alphanumeric() || "-_+.*/<>=!?$%&~^:".contains(c));
+        if is_symbol {
+            Ok(())
+        } else {
+            Err(ThemeError {
+                message: format!("'{}' is not a valid Emacs theme name", theme),
+                app_name: "emacs".to_string(),
+            })
+        }
+    }
+
+    /// Returns the Elisp that replaces all enabled themes with `theme`
+    pub fn switch_expression(theme: &str) -> String {
+        format!(
+            "(progn (mapc #'disable-theme custom-enabled-themes) (load-theme '{} t))",
+            theme
+        )
+    }
+
+    /// Returns the contents of the generated file for `theme`
+    pub fn render_theme_file(theme: &str) -> String {
+        format!(
+            ";;; {}.el --- Generated by themesync; changes are overwritten on the next theme switch.\n\
+             ;; Load it from init.el with: (load (locate-user-emacs-file \"{}\") t)\n\
+             (mapc #'disable-theme custom-enabled-themes)\n\
+             (load-theme '{} t)\n",
+            FILE_STEM, FILE_STEM, theme
+        )
+    }
+
+    /// Returns the theme loaded by the generated file
+    pub fn extract_theme_from_file(contents: &str) -> Option<String> {
+        contents.lines().find_map(|line| {
+            let rest = line.trim().strip_prefix("(load-theme '")?;
+            let end = rest.find(|c: char| c.is_whitespace() || c == ')')?;
+            Some(rest[..end].to_string())
+        })
+    }
+
+    /// Returns the first theme in a printed `custom-enabled-themes` list, e.g. `(modus-vivendi)`
+    pub fn parse_enabled_themes(output: &str) -> Option<String> {
+        output
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split_whitespace()
+            .next()
+            .filter(|theme| *theme != "nil")
+            .map(str::to_string)
+    }
+
+    /// Whether `init` loads the generated file outside of a comment
+    fn loads_theme_file(init: &str) -> bool {
+        init.lines().any(|line| {
+            let code = line.split(';').next().unwrap_or_default();
+            code.contains("load") && code.contains(FILE_STEM)
+        })
+    }
+
+    fn apply_to_file(&self, theme: &str) -> Result<(), ThemeError> {
+        write_atomic(&self.theme_file, Self::render_theme_file(theme)).map_err(|e| ThemeError {
+            message: format!("Failed to write {}: {}", self.theme_file.display(), e),
+            app_name: "emacs".to_string(),
+        })
+    }
+
+    async fn eval(&self, expression: &str) -> Result<String, ThemeError> {
+        let output = self
+            .runner
+            .run("emacsclient", &["--eval", expression])
+            .await
+            .map_err(|e| ThemeError {
+                message: format!("Failed to run emacsclient: {}", e),
+                app_name: "emacs".to_string(),
+            })?;
+
+        if output.success {
+            Ok(output.stdout)
+        } else {
+            Err(ThemeError {
+                message: format!("emacsclient failed: {}", output.stderr.trim()),
+                app_name: "emacs".to_string(),
+            })
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for EmacsAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        self.set_theme_with(theme, ApplyMethod::Auto).await
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        // The running server knows best, e.g. after a manual M-x load-theme
+        if let Ok(output) = self.eval("custom-enabled-themes").await {
+            let theme = Self::parse_enabled_themes(&output);
+            return Ok(theme.unwrap_or_else(|| "default".to_string()));
+        }
+
+        if !self.theme_file.exists() {
+            return Ok("default".to_string()); // Emacs built-in faces
+        }
+        let contents = std::fs::read_to_string(&self.theme_file).map_err(|e| ThemeError {
+            message: format!("Failed to read {}: {}", self.theme_file.display(), e),
+            app_name: "emacs".to_string(),
+        })?;
+        Ok(Self::extract_theme_from_file(&contents).unwrap_or_else(|| "default".to_string()))
+    }
+
+    async fn is_available(&self) -> bool {
+        let emacs_paths = [
+            "/Applications/Emacs.app/Contents/MacOS/Emacs",
+            "/usr/local/bin/emacs",
+            "/usr/bin/emacs",
+            "/opt/homebrew/bin/emacs",
+        ];
+
+        if emacs_paths.iter().any(|path| PathBuf::from(path).exists()) {
+            return true;
+        }
+
+        self.runner
+            .run("emacs", &["--version"])
+            .await
+            .is_ok_and(|output| output.success)
+    }
+
+    fn app_name(&self) -> &str {
+        "Emacs"
+    }
+
+    fn config_key(&self) -> &str {
+        "emacs"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "modus-vivendi".to_string());
+        themes.insert("light".to_string(), "modus-operandi".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.theme_file.clone()]
+    }
+
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::File, ApplyMethod::Ipc]
+    }
+
+    async fn set_theme_with(&self, theme: &str, method: ApplyMethod) -> Result<(), ThemeError> {
+        Self::validate_theme(theme)?;
+
+        match method {
+            ApplyMethod::File => self.apply_to_file(theme),
+            ApplyMethod::Ipc => self.eval(&Self::switch_expression(theme)).await.map(|_| ()),
+            // Persist the theme, then switch the server if one is running
+            ApplyMethod::Auto => {
+                self.apply_to_file(theme)?;
+                let _ = self.eval(&Self::switch_expression(theme)).await;
+                Ok(())
+            }
+            method @ ApplyMethod::Command => Err(unsupported_method(self, method)),
+        }
+    }
+
+    async fn doctor(&self) -> Vec<String> {
+        let Some(init_dir) = self.theme_file.parent() else {
+            return Vec::new();
+        };
+        let mut init_files = vec![init_dir.join("init.el")];
+        if let Some(home) = dirs::home_dir() {
+            init_files.push(home.join(".emacs"));
+            init_files.push(home.join(".emacs.el"));
+        }
+        let hint = format!("add (load (locate-user-emacs-file \"{}\") t)", FILE_STEM);
+
+        let Some(init_file) = init_files.iter().find(|path| path.is_file()) else {
+            return vec![format!(
+                "No init.el found in {}; {}",
+                init_dir.display(),
+                hint
+            )];
+        };
+
+        match std::fs::read_to_string(init_file) {
+            Ok(init) if Self::loads_theme_file(&init) => Vec::new(),
+            Ok(_) => vec![format!(
+                "{} does not load the generated theme; {}",
+                init_file.display(),
+                hint
+            )],
+            Err(e) => vec![format!("Failed to read {}: {}", init_file.display(), e)],
+        }
+    }
+}
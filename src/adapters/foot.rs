@@ -0,0 +1,184 @@
+//! Adapter for foot theme synchronization via an `include=` line in `foot.ini`.
+//!
+//! foot ships its themes as small ini files holding a `[colors]` section. Switching
+//! points an `include=` line in the `[main]` section at `<themes_dir>/<theme>`; other
+//! `include=` lines are left alone. foot reads its config on startup only, so running
+//! terminals (and `foot --server`) keep their colors until restarted.
+//!
+//! # Configuration Locations
+//! - Linux: `~/.config/foot/foot.ini`
+//!
+//! # Default Themes
+//! - dark: tokyonight-storm
+//! - light: tokyonight-day
+
+use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::config::expand_home;
+use crate::ini;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub struct FootAdapter {
+    config_path: PathBuf,
+    themes_dir: PathBuf,
+}
+
+impl Default for FootAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FootAdapter {
+    pub fn new() -> Self {
+        let config_path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("foot")
+            .join("foot.ini");
+
+        Self::with_path(config_path)
+    }
+
+    /// Creates an adapter that keeps the theme `include=` line in the `foot.ini` at `config_path`
+    pub fn with_path(config_path: PathBuf) -> Self {
+        Self {
+            config_path,
+            themes_dir: PathBuf::from("/usr/share/foot/themes"),
+        }
+    }
+
+    /// Looks for theme files in `themes_dir` instead of `/usr/share/foot/themes`
+    pub fn with_themes_dir(self, themes_dir: PathBuf) -> Self {
+        Self { themes_dir, ..self }
+    }
+
+    /// Returns the section holding `[main]` options; before any header they need none
+    fn main_section(contents: &str) -> &'static str {
+        if ini::sections(contents)
+            .iter()
+            .any(|section| section == "main")
+        {
+            "main"
+        } else {
+            ""
+        }
+    }
+
+    /// Whether an `include=` value points into the themes directory; foot expands `~` itself
+    fn is_theme_include(&self, value: &str) -> bool {
+        expand_home(Path::new(value)).starts_with(&self.themes_dir)
+    }
+
+    /// Points the theme `include=` line of `contents` at `theme_path`
+    pub fn update_include(&self, contents: &str, theme_path: &Path) -> String {
+        ini::set_where(
+            contents,
+            Self::main_section(contents),
+            "include",
+            &theme_path.to_string_lossy(),
+            |value| self.is_theme_include(value),
+        )
+    }
+
+    /// Returns the theme the `include=` lines of `contents` load from the themes directory
+    pub fn extract_theme(&self, contents: &str) -> Option<String> {
+        ini::entries(contents, Self::main_section(contents))
+            .into_iter()
+            .rev()
+            .filter(|(key, value)| key == "include" && self.is_theme_include(value))
+            .find_map(|(_, value)| {
+                let path = PathBuf::from(value);
+                Some(path.file_name()?.to_string_lossy().into_owned())
+            })
+    }
+
+    fn read_contents(&self) -> Result<String, ThemeError> {
+        if !self.config_path.exists() {
+            return Ok(String::new());
+        }
+
+        std::fs::read_to_string(&self.config_path).map_err(|e| ThemeError {
+            message: format!("Failed to read foot.ini: {}", e),
+            app_name: "foot".to_string(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for FootAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let theme_path = self.themes_dir.join(theme);
+        if !theme_path.is_file() {
+            return Err(ThemeError {
+                message: format!("Theme file {} not found", theme_path.display()),
+                app_name: "foot".to_string(),
+            });
+        }
+
+        let updated = self.update_include(&self.read_contents()?, &theme_path);
+        write_atomic(&self.config_path, updated).map_err(|e| ThemeError {
+            message: format!("Failed to write foot.ini: {}", e),
+            app_name: "foot".to_string(),
+        })
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let contents = self.read_contents()?;
+        Ok(self
+            .extract_theme(&contents)
+            .unwrap_or_else(|| "default".to_string())) // foot built-in colors
+    }
+
+    async fn is_available(&self) -> bool {
+        let foot_paths = ["/usr/bin/foot", "/usr/local/bin/foot"];
+        foot_paths.iter().any(|path| PathBuf::from(path).exists())
+    }
+
+    fn app_name(&self) -> &str {
+        "foot"
+    }
+
+    fn config_key(&self) -> &str {
+        "foot"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "tokyonight-storm".to_string());
+        themes.insert("light".to_string(), "tokyonight-day".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.config_path.clone()]
+    }
+
+    fn needs_restart(&self) -> bool {
+        true
+    }
+
+    async fn doctor(&self) -> Vec<String> {
+        let Ok(contents) = self.read_contents() else {
+            return Vec::new();
+        };
+
+        // Colors set in foot.ini itself are applied after the include and win
+        let overrides: Vec<String> = ["colors", "colors-dark", "colors-light"]
+            .iter()
+            .flat_map(|section| ini::entries(&contents, section))
+            .map(|(key, _)| key)
+            .filter(|key| key != "alpha")
+            .collect();
+        if overrides.is_empty() {
+            Vec::new()
+        } else {
+            vec![format!(
+                "{} sets {} itself, overriding the theme; remove them from its [colors] section",
+                self.config_path.display(),
+                overrides.join(", ")
+            )]
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::adapter::{ThemeAdapter, ThemeError, ThemePair};
+use crate::atomic_write::write_atomic;
 use anyhow::Result;
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process::Command;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
 
 pub struct GhosttyAdapter {
     config_path: PathBuf,
@@ -21,36 +22,95 @@ impl GhosttyAdapter {
             .join("ghostty")
             .join("config");
 
+        Self::with_path(config_path)
+    }
+
+    /// Creates an adapter for the Ghostty config at `config_path` and the files it includes
+    pub fn with_path(config_path: PathBuf) -> Self {
         Self { config_path }
     }
 
-    fn read_config(&self) -> Result<String, ThemeError> {
-        if !self.config_path.exists() {
+    fn read_config(path: &Path) -> Result<String, ThemeError> {
+        if !path.exists() {
             return Ok(String::new());
         }
 
-        std::fs::read_to_string(&self.config_path).map_err(|e| ThemeError {
-            message: format!("Failed to read Ghostty config: {}", e),
+        std::fs::read_to_string(path).map_err(|e| ThemeError {
+            message: format!("Failed to read Ghostty config {}: {}", path.display(), e),
             app_name: "ghostty".to_string(),
         })
     }
 
-    fn write_config(&self, content: &str) -> Result<(), ThemeError> {
-        if let Some(parent) = self.config_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| ThemeError {
-                message: format!("Failed to create Ghostty config directory: {}", e),
-                app_name: "ghostty".to_string(),
-            })?;
-        }
-
-        std::fs::write(&self.config_path, content).map_err(|e| ThemeError {
-            message: format!("Failed to write Ghostty config: {}", e),
+    fn write_config(path: &Path, content: &str) -> Result<(), ThemeError> {
+        write_atomic(path, content).map_err(|e| ThemeError {
+            message: format!("Failed to write Ghostty config {}: {}", path.display(), e),
             app_name: "ghostty".to_string(),
         })?;
 
         Ok(())
     }
 
+    /// Returns the `config-file` includes of `config`, resolved against `base_dir`
+    ///
+    /// Optional includes (`?path`) are returned like any other; callers skip missing files.
+    pub fn parse_includes(config: &str, base_dir: &Path) -> Vec<PathBuf> {
+        Self::parse_config_lines(config)
+            .into_iter()
+            .filter(|(key, _)| key == "config-file")
+            .filter_map(|(_, value)| {
+                let value = value.trim_matches('"');
+                let path = value.strip_prefix('?').unwrap_or(value).trim_matches('"');
+                if path.is_empty() {
+                    return None;
+                }
+
+                match (path.strip_prefix("~/"), dirs::home_dir()) {
+                    (Some(rest), Some(home)) => Some(home.join(rest)),
+                    _ => Some(base_dir.join(path)),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the main config and every existing include, in the order Ghostty loads them
+    ///
+    /// Ghostty processes a file's `config-file` entries after the file itself, so an
+    /// include can override anything set before it.
+    fn config_chain(&self) -> Result<Vec<(PathBuf, String)>, ThemeError> {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        self.load_into_chain(&self.config_path, &mut chain, &mut visited)?;
+
+        if chain.is_empty() {
+            chain.push((self.config_path.clone(), String::new()));
+        }
+        Ok(chain)
+    }
+
+    fn load_into_chain(
+        &self,
+        path: &Path,
+        chain: &mut Vec<(PathBuf, String)>,
+        visited: &mut HashSet<PathBuf>,
+    ) -> Result<(), ThemeError> {
+        // Guard against include cycles
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if !path.is_file() || !visited.insert(key) {
+            return Ok(());
+        }
+
+        let contents = Self::read_config(path)?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let includes = Self::parse_includes(&contents, base_dir);
+        chain.push((path.to_path_buf(), contents));
+
+        // Missing includes, optional or not, contribute no settings
+        for include in includes {
+            self.load_into_chain(&include, chain, visited)?;
+        }
+        Ok(())
+    }
+
     pub fn parse_config_lines(config: &str) -> Vec<(String, String)> {
         config
             .lines()
@@ -69,30 +129,52 @@ impl GhosttyAdapter {
             .collect()
     }
 
-    fn format_config_lines(lines: &[(String, String)]) -> String {
-        lines
-            .iter()
-            .map(|(key, value)| format!("{} = {}", key, value))
-            .collect::<Vec<_>>()
-            .join("\n")
+    /// Returns the key of a `key = value` line, or `None` for comments and blank lines
+    fn line_key(line: &str) -> Option<&str> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        line.split_once('=').map(|(key, _)| key.trim())
     }
 
+    /// Points the effective `theme` line at `new_theme`
+    ///
+    /// Only that line changes: comments, blank lines and the order of repeated keys
+    /// such as `keybind` are preserved. Ghostty lets later lines win, so the last
+    /// `theme` line is the one replaced; a new line is appended when there is none.
     pub fn update_theme_in_config(config: &str, new_theme: &str) -> String {
-        let mut lines = Self::parse_config_lines(config);
-
-        // Remove existing theme line
-        lines.retain(|(key, _)| key != "theme");
+        let theme_line = format!("theme = {}", new_theme);
+        let mut lines: Vec<&str> = config.split_inclusive('\n').collect();
 
-        // Add new theme line
-        lines.push(("theme".to_string(), new_theme.to_string()));
-
-        Self::format_config_lines(&lines)
+        match lines
+            .iter()
+            .rposition(|line| Self::line_key(line) == Some("theme"))
+        {
+            Some(index) => {
+                let line = lines[index];
+                let content = line.trim_end_matches(['\r', '\n']);
+                let ending = &line[content.len()..];
+                let replaced = format!("{}{}", theme_line, ending);
+                lines[index] = &replaced;
+                lines.concat()
+            }
+            None => {
+                let mut updated = config.to_string();
+                if !updated.is_empty() && !updated.ends_with('\n') {
+                    updated.push('\n');
+                }
+                updated.push_str(&theme_line);
+                updated.push('\n');
+                updated
+            }
+        }
     }
 
     pub fn extract_theme_from_config(config: &str) -> Option<String> {
         Self::parse_config_lines(config)
             .into_iter()
-            .find(|(key, _)| key == "theme")
+            .rfind(|(key, _)| key == "theme")
             .map(|(_, value)| value)
     }
 }
@@ -100,16 +182,31 @@ impl GhosttyAdapter {
 #[async_trait::async_trait]
 impl ThemeAdapter for GhosttyAdapter {
     async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
-        let current_config = self.read_config()?;
-        let updated_config = Self::update_theme_in_config(&current_config, theme);
-        self.write_config(&updated_config)?;
+        let theme = match ThemePair::parse(theme) {
+            Some(pair) => pair.to_string(),
+            None => theme.to_string(),
+        };
+
+        // Edit the file holding the effective theme line, falling back to the main config
+        let chain = self.config_chain()?;
+        let (path, contents) = chain
+            .iter()
+            .rfind(|(_, contents)| Self::extract_theme_from_config(contents).is_some())
+            .unwrap_or(&chain[0]);
+
+        let updated_config = Self::update_theme_in_config(contents, &theme);
+        Self::write_config(path, &updated_config)?;
         Ok(())
     }
 
     async fn get_theme(&self) -> Result<String, ThemeError> {
-        let config = self.read_config()?;
+        let theme = self
+            .config_chain()?
+            .iter()
+            .filter_map(|(_, contents)| Self::extract_theme_from_config(contents))
+            .next_back();
 
-        match Self::extract_theme_from_config(&config) {
+        match theme {
             Some(theme) => Ok(theme),
             None => Ok("default".to_string()), // Ghostty default
         }
@@ -135,7 +232,9 @@ impl ThemeAdapter for GhosttyAdapter {
         // Also try to run the command to see if it's in PATH
         Command::new("ghostty")
             .arg("--version")
+            .kill_on_drop(true)
             .output()
+            .await
             .map(|output| output.status.success())
             .unwrap_or(false)
     }
@@ -154,4 +253,11 @@ impl ThemeAdapter for GhosttyAdapter {
         themes.insert("light".to_string(), "catppuccin-latte".to_string());
         themes
     }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        match self.config_chain() {
+            Ok(chain) => chain.into_iter().map(|(path, _)| path).collect(),
+            Err(_) => vec![self.config_path.clone()],
+        }
+    }
 }
@@ -0,0 +1,288 @@
+//! Adapter for GNOME Terminal theme synchronization via its dconf profile.
+//!
+//! Themes are files in the themes directory holding profile keys as printed by
+//! `dconf dump` (`palette=[...]`, `background-color='#...'`, ...). Switching writes
+//! them to the default profile with the `dconf` CLI, which GNOME Terminal applies to
+//! open windows right away. The theme `system` switches back to the desktop's colors,
+//! and `tango-dark`/`tango-light` are GNOME Terminal's own Tango schemes, built in so
+//! they work without a theme file.
+//!
+//! # Configuration Locations
+//! - Linux: dconf `/org/gnome/terminal/legacy/profiles:/:<default profile>/`
+//! - Themes: `~/.config/themesync/themes/gnome-terminal/<theme>.dconf`
+//!
+//! # Default Themes
+//! - dark: tango-dark
+//! - light: tango-light
+
+use crate::adapter::{ApplyMethod, ThemeAdapter, ThemeError};
+use crate::ini;
+use crate::runner::{CommandRunner, SystemRunner};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const PROFILES_PATH: &str = "/org/gnome/terminal/legacy/profiles:";
+/// Profile GNOME Terminal creates when none is configured
+const BUILTIN_PROFILE: &str = "b1dcc9dd-5262-4d8d-a863-c897e6d979b9";
+const SYSTEM_THEME: &str = "system";
+
+const TANGO_PALETTE: &str = "['#2E3436', '#CC0000', '#4E9A06', '#C4A000', '#3465A4', \
+    '#75507B', '#06989A', '#D3D7CF', '#555753', '#EF2929', '#8AE234', '#FCE94F', \
+    '#729FCF', '#AD7FA8', '#34E2E2', '#EEEEEC']";
+
+/// Themes available without a file; a `<theme>.dconf` of the same name takes precedence
+const BUILTIN_THEMES: [(&str, &[(&str, &str)]); 2] = [
+    (
+        "tango-dark",
+        &[
+            ("palette", TANGO_PALETTE),
+            ("background-color", "'#2E3436'"),
+            ("foreground-color", "'#D3D7CF'"),
+        ],
+    ),
+    (
+        "tango-light",
+        &[
+            ("palette", TANGO_PALETTE),
+            ("background-color", "'#EEEEEC'"),
+            ("foreground-color", "'#2E3436'"),
+        ],
+    ),
+];
+
+pub struct GnomeTerminalAdapter {
+    themes_dir: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for GnomeTerminalAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GnomeTerminalAdapter {
+    pub fn new() -> Self {
+        let themes_dir = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("themesync")
+            .join("themes")
+            .join("gnome-terminal");
+
+        Self {
+            themes_dir,
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Reads theme keys from the `<theme>.dconf` profile exports in `themes_dir`
+    pub fn with_themes_dir(self, themes_dir: PathBuf) -> Self {
+        Self { themes_dir, ..self }
+    }
+
+    /// Reads, writes and dumps the default profile with `dconf` through `runner`
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    fn error(message: String) -> ThemeError {
+        ThemeError {
+            message,
+            app_name: "gnome-terminal".to_string(),
+        }
+    }
+
+    /// Returns the profile keys of a theme file; `dconf dump` output has them under `[/]`
+    pub fn parse_theme_file(contents: &str) -> Vec<(String, String)> {
+        let mut keys = ini::entries(contents, "");
+        keys.extend(ini::entries(contents, "/"));
+        keys
+    }
+
+    async fn dconf(&self, args: &[&str]) -> Result<String, ThemeError> {
+        let output = self
+            .runner
+            .run("dconf", args)
+            .await
+            .map_err(|e| Self::error(format!("Failed to run dconf: {}", e)))?;
+
+        if output.success {
+            Ok(output.stdout.trim().to_string())
+        } else {
+            Err(Self::error(format!(
+                "dconf {} failed: {}",
+                args[0],
+                output.stderr.trim()
+            )))
+        }
+    }
+
+    /// Returns the dconf directory of the profile new windows open with
+    async fn default_profile_path(&self) -> Result<String, ThemeError> {
+        let default = self
+            .dconf(&["read", &format!("{}/default", PROFILES_PATH)])
+            .await?;
+        let uuid = match default.trim_matches('\'') {
+            "" => {
+                let list = self
+                    .dconf(&["read", &format!("{}/list", PROFILES_PATH)])
+                    .await?;
+                list.trim_matches(|c| c == '[' || c == ']')
+                    .split(',')
+                    .map(|uuid| uuid.trim().trim_matches('\'').to_string())
+                    .find(|uuid| !uuid.is_empty())
+                    .unwrap_or_else(|| BUILTIN_PROFILE.to_string())
+            }
+            uuid => uuid.to_string(),
+        };
+        Ok(format!("{}/:{}/", PROFILES_PATH, uuid))
+    }
+
+    fn builtin_theme(theme: &str) -> Option<Vec<(String, String)>> {
+        let (_, keys) = BUILTIN_THEMES.iter().find(|(name, _)| *name == theme)?;
+        Some(
+            keys.iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    fn read_theme(&self, theme: &str) -> Result<Vec<(String, String)>, ThemeError> {
+        let path = self.themes_dir.join(format!("{}.dconf", theme));
+        if !path.is_file() {
+            return Self::builtin_theme(theme)
+                .ok_or_else(|| Self::error(format!("Theme file {} not found", path.display())));
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| Self::error(format!("Failed to read {}: {}", path.display(), e)))?;
+        Ok(Self::parse_theme_file(&contents))
+    }
+
+    /// Returns the name of the theme, from a file or built in, whose keys all match the profile
+    async fn matching_theme(&self, profile: &str) -> Option<String> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.themes_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "dconf"))
+            .collect();
+        paths.sort();
+
+        let mut themes: Vec<(String, Vec<(String, String)>)> = paths
+            .iter()
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_string_lossy().into_owned();
+                let contents = std::fs::read_to_string(path).ok()?;
+                Some((name, Self::parse_theme_file(&contents)))
+            })
+            .collect();
+        for (name, _) in BUILTIN_THEMES {
+            if !themes.iter().any(|(theme, _)| theme == name) {
+                themes.extend(Self::builtin_theme(name).map(|keys| (name.to_string(), keys)));
+            }
+        }
+
+        for (name, keys) in themes {
+            let mut matches = !keys.is_empty();
+            for (key, value) in &keys {
+                let current = self.dconf(&["read", &format!("{}{}", profile, key)]).await;
+                matches &= current.is_ok_and(|current| current == *value);
+            }
+            if matches {
+                return Some(name);
+            }
+        }
+        None
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for GnomeTerminalAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let profile = self.default_profile_path().await?;
+        let use_theme_colors = format!("{}use-theme-colors", profile);
+        if theme == SYSTEM_THEME {
+            return self
+                .dconf(&["write", &use_theme_colors, "true"])
+                .await
+                .map(|_| ());
+        }
+
+        for (key, value) in self.read_theme(theme)? {
+            self.dconf(&["write", &format!("{}{}", profile, key), &value])
+                .await?;
+        }
+        // The palette is ignored while the profile follows the desktop colors
+        self.dconf(&["write", &use_theme_colors, "false"])
+            .await
+            .map(|_| ())
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        // Without dconf or a profile, GNOME Terminal would start with the desktop colors
+        let Ok(profile) = self.default_profile_path().await else {
+            return Ok(SYSTEM_THEME.to_string());
+        };
+        let use_theme_colors = self
+            .dconf(&["read", &format!("{}use-theme-colors", profile)])
+            .await
+            .unwrap_or_default();
+        // Unset means the default, which is to follow the desktop
+        if use_theme_colors != "false" {
+            return Ok(SYSTEM_THEME.to_string());
+        }
+
+        Ok(self
+            .matching_theme(&profile)
+            .await
+            .unwrap_or_else(|| "custom".to_string()))
+    }
+
+    async fn is_available(&self) -> bool {
+        let terminal_paths = ["/usr/bin/gnome-terminal", "/usr/local/bin/gnome-terminal"];
+        terminal_paths
+            .iter()
+            .any(|path| PathBuf::from(path).exists())
+    }
+
+    fn app_name(&self) -> &str {
+        "GNOME Terminal"
+    }
+
+    fn config_key(&self) -> &str {
+        "gnome-terminal"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "tango-dark".to_string());
+        themes.insert("light".to_string(), "tango-light".to_string());
+        themes
+    }
+
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::Command]
+    }
+
+    /// Returns every key of the default profile as printed by `dconf dump`
+    async fn capture_state(&self) -> Result<Option<String>, ThemeError> {
+        let profile = self.default_profile_path().await?;
+        let dump = self.dconf(&["dump", &profile]).await?;
+        Ok(Some(format!("{}\n{}", profile, dump)))
+    }
+
+    /// Resets the profile captured by `capture_state` and writes its keys back
+    async fn restore_state(&self, state: &str) -> Result<(), ThemeError> {
+        let (profile, dump) = state.split_once('\n').unwrap_or((state, ""));
+        self.dconf(&["reset", "-f", profile]).await?;
+        for (key, value) in Self::parse_theme_file(dump) {
+            self.dconf(&["write", &format!("{}{}", profile, key), &value])
+                .await?;
+        }
+        Ok(())
+    }
+}
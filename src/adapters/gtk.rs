@@ -0,0 +1,309 @@
+//! Adapter for GTK / GNOME appearance synchronization via `settings.ini` and `gsettings`.
+//!
+//! A theme value is the GTK theme name, optionally followed by `|` and an icon theme,
+//! e.g. `Adwaita-dark|Papirus-Dark`. A `dark:` or `light:` prefix sets the dark-variant
+//! preference explicitly; without one it is inferred from the theme name. When
+//! `gsettings` is available, GNOME's `color-scheme`, `gtk-theme` and `icon-theme` are
+//! set too, so running apps follow immediately.
+//!
+//! # Configuration Locations
+//! - Linux: `~/.config/gtk-3.0/settings.ini` and `~/.config/gtk-4.0/settings.ini`
+//!
+//! # Default Themes
+//! - dark: dark:Adwaita
+//! - light: light:Adwaita
+
+use crate::adapter::{unsupported_method, ApplyMethod, ThemeAdapter, ThemeError};
+use crate::appearance::Appearance;
+use crate::atomic_write::write_atomic;
+use crate::ini;
+use crate::runner::{CommandRunner, SystemRunner};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SECTION: &str = "Settings";
+const GSETTINGS_SCHEMA: &str = "org.gnome.desktop.interface";
+/// Desktop keys `apply_gsettings` may change
+const GSETTINGS_KEYS: [&str; 3] = ["color-scheme", "gtk-theme", "icon-theme"];
+
+/// A parsed GTK theme value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GtkTheme {
+    pub theme: String,
+    pub icon_theme: Option<String>,
+    pub appearance: Appearance,
+}
+
+impl GtkTheme {
+    /// Parses `[dark:|light:]THEME[|ICONS]`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (appearance, rest) = match value.trim().split_once(':') {
+            Some(("dark", rest)) => (Some(Appearance::Dark), rest),
+            Some(("light", rest)) => (Some(Appearance::Light), rest),
+            _ => (None, value.trim()),
+        };
+        let (theme, icon_theme) = match rest.split_once('|') {
+            Some((theme, icons)) => (theme.trim(), Some(icons.trim()).filter(|i| !i.is_empty())),
+            None => (rest.trim(), None),
+        };
+        if theme.is_empty() {
+            return None;
+        }
+
+        let appearance = appearance.unwrap_or(if theme.to_lowercase().contains("dark") {
+            Appearance::Dark
+        } else {
+            Appearance::Light
+        });
+        Some(Self {
+            theme: theme.to_string(),
+            icon_theme: icon_theme.map(str::to_string),
+            appearance,
+        })
+    }
+}
+
+pub struct GtkAdapter {
+    config_dir: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for GtkAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GtkAdapter {
+    pub fn new() -> Self {
+        // GTK reads $XDG_CONFIG_HOME, which is ~/.config unless overridden
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        Self::with_path(config_dir)
+    }
+
+    /// Creates an adapter that edits `gtk-3.0`/`gtk-4.0` under `config_dir` instead of `~/.config`
+    pub fn with_path(config_dir: PathBuf) -> Self {
+        Self {
+            config_dir,
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Sets the `org.gnome.desktop.interface` keys with `gsettings` through `runner`
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    fn settings_files(&self) -> [PathBuf; 2] {
+        ["gtk-3.0", "gtk-4.0"].map(|dir| self.config_dir.join(dir).join("settings.ini"))
+    }
+
+    fn error(message: String) -> ThemeError {
+        ThemeError {
+            message,
+            app_name: "gtk".to_string(),
+        }
+    }
+
+    fn parse_theme(theme: &str) -> Result<GtkTheme, ThemeError> {
+        GtkTheme::parse(theme).ok_or_else(|| Self::error(format!("Invalid GTK theme '{}'", theme)))
+    }
+
+    /// Writes `theme` into the `[Settings]` group of a `settings.ini`, keeping comments
+    pub fn update_settings(contents: &str, theme: &GtkTheme) -> String {
+        let prefer_dark = match theme.appearance {
+            Appearance::Dark => "true",
+            Appearance::Light => "false",
+        };
+        let mut contents = ini::set(contents, SECTION, "gtk-theme-name", &theme.theme);
+        contents = ini::set(
+            &contents,
+            SECTION,
+            "gtk-application-prefer-dark-theme",
+            prefer_dark,
+        );
+        if let Some(icon_theme) = &theme.icon_theme {
+            contents = ini::set(&contents, SECTION, "gtk-icon-theme-name", icon_theme);
+        }
+        contents
+    }
+
+    /// Returns the theme value described by the contents of a `settings.ini`
+    pub fn extract_theme(contents: &str) -> Option<String> {
+        let theme = ini::get(contents, SECTION, "gtk-theme-name")?;
+        let prefix =
+            match ini::get(contents, SECTION, "gtk-application-prefer-dark-theme").as_deref() {
+                Some("true" | "1") => "dark:",
+                Some("false" | "0") => "light:",
+                _ => "",
+            };
+        let icons = ini::get(contents, SECTION, "gtk-icon-theme-name")
+            .map(|icons| format!("|{}", icons))
+            .unwrap_or_default();
+        Some(format!("{}{}{}", prefix, theme, icons))
+    }
+
+    fn apply_to_file(&self, path: &Path, theme: &GtkTheme) -> Result<(), ThemeError> {
+        let contents = if path.exists() {
+            std::fs::read_to_string(path)
+                .map_err(|e| Self::error(format!("Failed to read {}: {}", path.display(), e)))?
+        } else {
+            String::new()
+        };
+
+        write_atomic(path, Self::update_settings(&contents, theme))
+            .map_err(|e| Self::error(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    fn apply_to_files(&self, theme: &GtkTheme) -> Result<(), ThemeError> {
+        for path in self.settings_files() {
+            self.apply_to_file(&path, theme)?;
+        }
+        Ok(())
+    }
+
+    async fn gsettings(
+        &self,
+        action: &str,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<String, ThemeError> {
+        let mut args = vec![action, GSETTINGS_SCHEMA, key];
+        args.extend(value);
+        let output = self
+            .runner
+            .run("gsettings", &args)
+            .await
+            .map_err(|e| Self::error(format!("Failed to run gsettings: {}", e)))?;
+
+        if output.success {
+            Ok(output.stdout.trim().to_string())
+        } else {
+            Err(Self::error(format!(
+                "gsettings {} {} failed: {}",
+                action,
+                key,
+                output.stderr.trim()
+            )))
+        }
+    }
+
+    async fn gsettings_set(&self, key: &str, value: &str) -> Result<(), ThemeError> {
+        // Quoted as a GVariant string so names like `true` aren't parsed as other types
+        let value = format!("'{}'", value.replace('\'', "\\'"));
+        self.gsettings("set", key, Some(&value)).await.map(|_| ())
+    }
+
+    /// Applies `theme` to the GNOME desktop settings running apps listen to
+    async fn apply_gsettings(&self, theme: &GtkTheme) -> Result<(), ThemeError> {
+        let color_scheme = match theme.appearance {
+            Appearance::Dark => "prefer-dark",
+            Appearance::Light => "default",
+        };
+        self.gsettings_set("color-scheme", color_scheme).await?;
+        self.gsettings_set("gtk-theme", &theme.theme).await?;
+        if let Some(icon_theme) = &theme.icon_theme {
+            self.gsettings_set("icon-theme", icon_theme).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for GtkAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        self.set_theme_with(theme, ApplyMethod::Auto).await
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        for path in self.settings_files() {
+            if !path.exists() {
+                continue;
+            }
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| Self::error(format!("Failed to read {}: {}", path.display(), e)))?;
+            if let Some(theme) = Self::extract_theme(&contents) {
+                return Ok(theme);
+            }
+        }
+
+        Ok("Adwaita".to_string()) // GTK default
+    }
+
+    async fn is_available(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            return false;
+        }
+
+        self.settings_files()
+            .iter()
+            .any(|path| path.parent().is_some_and(Path::is_dir))
+            || PathBuf::from("/usr/share/themes").is_dir()
+    }
+
+    fn app_name(&self) -> &str {
+        "GTK"
+    }
+
+    fn config_key(&self) -> &str {
+        "gtk"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "dark:Adwaita".to_string());
+        themes.insert("light".to_string(), "light:Adwaita".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        self.settings_files().to_vec()
+    }
+
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::File, ApplyMethod::Command]
+    }
+
+    /// Returns the GNOME desktop keys `set_theme` changes, as `key=<GVariant>` lines;
+    /// `None` outside GNOME, where there is no gsettings to restore
+    async fn capture_state(&self) -> Result<Option<String>, ThemeError> {
+        let mut state = String::new();
+        for key in GSETTINGS_KEYS {
+            match self.gsettings("get", key, None).await {
+                Ok(value) => state.push_str(&format!("{}={}\n", key, value)),
+                Err(_) => return Ok(None),
+            }
+        }
+        Ok(Some(state))
+    }
+
+    async fn restore_state(&self, state: &str) -> Result<(), ThemeError> {
+        // Values are GVariant text as printed by `gsettings get`, so they go back as is
+        for (key, value) in ini::entries(state, "") {
+            self.gsettings("set", &key, Some(&value)).await?;
+        }
+        Ok(())
+    }
+
+    async fn set_theme_with(&self, theme: &str, method: ApplyMethod) -> Result<(), ThemeError> {
+        let theme = Self::parse_theme(theme)?;
+
+        match method {
+            ApplyMethod::File => self.apply_to_files(&theme),
+            ApplyMethod::Command => self.apply_gsettings(&theme).await,
+            // Outside GNOME there is no gsettings, and settings.ini is all GTK reads
+            ApplyMethod::Auto => {
+                self.apply_to_files(&theme)?;
+                let _ = self.apply_gsettings(&theme).await;
+                Ok(())
+            }
+            method @ ApplyMethod::Ipc => Err(unsupported_method(self, method)),
+        }
+    }
+}
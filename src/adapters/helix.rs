@@ -1,16 +1,10 @@
 use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::process::Command;
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-struct HelixConfig {
-    theme: Option<String>,
-    #[serde(flatten)]
-    other: HashMap<String, toml::Value>,
-}
+use tokio::process::Command;
+use toml_edit::DocumentMut;
 
 pub struct HelixAdapter {
     config_path: PathBuf,
@@ -29,66 +23,85 @@ impl HelixAdapter {
             .join("helix")
             .join("config.toml");
 
+        Self::with_path(config_path)
+    }
+
+    /// Creates an adapter that sets the top-level `theme` key of the Helix `config.toml` at `config_path`
+    pub fn with_path(config_path: PathBuf) -> Self {
         Self { config_path }
     }
 
-    fn read_config(&self) -> Result<HelixConfig, ThemeError> {
+    fn read_config(&self) -> Result<String, ThemeError> {
         if !self.config_path.exists() {
-            return Ok(HelixConfig::default());
+            return Ok(String::new());
         }
 
-        let contents = std::fs::read_to_string(&self.config_path).map_err(|e| ThemeError {
+        std::fs::read_to_string(&self.config_path).map_err(|e| ThemeError {
             message: format!("Failed to read Helix config: {}", e),
             app_name: "helix".to_string(),
+        })
+    }
+
+    fn write_config(&self, contents: &str) -> Result<(), ThemeError> {
+        write_atomic(&self.config_path, contents).map_err(|e| ThemeError {
+            message: format!("Failed to write Helix config.toml: {}", e),
+            app_name: "helix".to_string(),
         })?;
 
-        if contents.trim().is_empty() {
-            return Ok(HelixConfig::default());
-        }
+        Ok(())
+    }
 
-        let config: HelixConfig = toml::from_str(&contents).map_err(|e| ThemeError {
+    fn parse_document(config: &str) -> Result<DocumentMut, ThemeError> {
+        config.parse::<DocumentMut>().map_err(|e| ThemeError {
             message: format!("Failed to parse Helix config.toml: {}", e),
             app_name: "helix".to_string(),
-        })?;
-
-        Ok(config)
+        })
     }
 
-    fn write_config(&self, config: &HelixConfig) -> Result<(), ThemeError> {
-        if let Some(parent) = self.config_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| ThemeError {
-                message: format!("Failed to create Helix config directory: {}", e),
-                app_name: "helix".to_string(),
-            })?;
+    /// Sets the top-level `theme` key, leaving comments, key order and formatting intact
+    pub fn update_theme_in_config(config: &str, new_theme: &str) -> Result<String, ThemeError> {
+        let mut document = Self::parse_document(config)?;
+
+        match document
+            .get_mut("theme")
+            .and_then(|item| item.as_value_mut())
+        {
+            Some(value) => {
+                // Keep the whitespace and any trailing comment around the old value
+                let decor = value.decor().clone();
+                *value = new_theme.into();
+                *value.decor_mut() = decor;
+            }
+            None => {
+                document.insert("theme", toml_edit::value(new_theme));
+            }
         }
 
-        let contents = toml::to_string_pretty(config).map_err(|e| ThemeError {
-            message: format!("Failed to serialize Helix config: {}", e),
-            app_name: "helix".to_string(),
-        })?;
-
-        std::fs::write(&self.config_path, contents).map_err(|e| ThemeError {
-            message: format!("Failed to write Helix config.toml: {}", e),
-            app_name: "helix".to_string(),
-        })?;
+        Ok(document.to_string())
+    }
 
-        Ok(())
+    pub fn extract_theme_from_config(config: &str) -> Result<Option<String>, ThemeError> {
+        let document = Self::parse_document(config)?;
+        Ok(document
+            .get("theme")
+            .and_then(|item| item.as_str())
+            .map(str::to_string))
     }
 }
 
 #[async_trait::async_trait]
 impl ThemeAdapter for HelixAdapter {
     async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
-        let mut config = self.read_config()?;
-        config.theme = Some(theme.to_string());
-        self.write_config(&config)?;
+        let config = self.read_config()?;
+        let updated = Self::update_theme_in_config(&config, theme)?;
+        self.write_config(&updated)?;
         Ok(())
     }
 
     async fn get_theme(&self) -> Result<String, ThemeError> {
         let config = self.read_config()?;
 
-        match config.theme {
+        match Self::extract_theme_from_config(&config)? {
             Some(theme) => Ok(theme),
             None => Ok("default".to_string()), // Helix default theme
         }
@@ -112,7 +125,9 @@ impl ThemeAdapter for HelixAdapter {
         // Also try to run the command to see if it's in PATH
         Command::new("hx")
             .arg("--version")
+            .kill_on_drop(true)
             .output()
+            .await
             .map(|output| output.status.success())
             .unwrap_or(false)
     }
@@ -131,4 +146,8 @@ impl ThemeAdapter for HelixAdapter {
         themes.insert("light".to_string(), "ayu_light".to_string());
         themes
     }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.config_path.clone()]
+    }
 }
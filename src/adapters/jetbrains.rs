@@ -0,0 +1,239 @@
+//! Adapter for JetBrains IDE theme synchronization via `laf.xml` and `colors.scheme.xml`.
+//!
+//! Every installed version of a product keeps its settings in its own directory, e.g.
+//! `RustRover2024.2/options/`. One adapter covers all versions of a product family and
+//! has its own config key (`jetbrains-rustrover`, `jetbrains-intellijidea`, ...).
+//!
+//! A theme value is the UI theme id, optionally followed by `|` and the editor color
+//! scheme, e.g. `ExperimentalDark|Dark`. IDEs only read these files on startup, so
+//! running instances pick the change up after a restart.
+//!
+//! # Configuration Locations
+//! - macOS: `~/Library/Application Support/JetBrains/<Product><Version>/options/`
+//! - Linux: `~/.config/JetBrains/<Product><Version>/options/`
+//!
+//! # Default Themes
+//! - dark: ExperimentalDark|Dark
+//! - light: ExperimentalLight|Light
+
+use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::xml::{self, Selector};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+const LAF_FILE: &str = "laf.xml";
+const SCHEME_FILE: &str = "colors.scheme.xml";
+
+fn laf_path() -> [Selector<'static>; 3] {
+    [
+        Selector::new("application"),
+        Selector::new("component").with_attribute("name", "LafManager"),
+        Selector::new("laf"),
+    ]
+}
+
+fn scheme_path() -> [Selector<'static>; 3] {
+    [
+        Selector::new("application"),
+        Selector::new("component").with_attribute("name", "EditorColorsManagerImpl"),
+        Selector::new("global_color_scheme"),
+    ]
+}
+
+/// Returns the display name of a product family, as used in its settings directory name
+fn family_name(family: &str) -> String {
+    let name = match family {
+        "IntelliJIdea" => "IntelliJ IDEA",
+        "IdeaIC" => "IntelliJ IDEA Community",
+        "PyCharmCE" => "PyCharm Community",
+        other => other,
+    };
+    name.to_string()
+}
+
+/// Splits a settings directory name like `RustRover2024.2` into family and version
+pub fn parse_product_dir(name: &str) -> Option<(&str, Vec<u32>)> {
+    let split = name.find(|c: char| c.is_ascii_digit())?;
+    let (family, version) = name.split_at(split);
+    if family.is_empty() || !family.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let version = version
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    Some((family, version))
+}
+
+pub struct JetBrainsAdapter {
+    /// Settings directories of each installed version, oldest first
+    product_dirs: Vec<PathBuf>,
+    config_key: String,
+    app_name: String,
+}
+
+impl JetBrainsAdapter {
+    /// Returns the directory holding the settings of every JetBrains product
+    pub fn default_root() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("JetBrains")
+    }
+
+    /// Creates one adapter per product family with settings under `root`
+    ///
+    /// Returns nothing when no JetBrains IDE has been run on this machine.
+    pub fn discover(root: &Path) -> Vec<Self> {
+        let mut families: BTreeMap<String, Vec<(Vec<u32>, PathBuf)>> = BTreeMap::new();
+        for entry in std::fs::read_dir(root).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some((family, version)) = parse_product_dir(&name) {
+                families
+                    .entry(family.to_string())
+                    .or_default()
+                    .push((version, path));
+            }
+        }
+
+        families
+            .into_iter()
+            .map(|(family, mut versions)| {
+                versions.sort();
+                Self {
+                    product_dirs: versions.into_iter().map(|(_, path)| path).collect(),
+                    config_key: format!("jetbrains-{}", family.to_lowercase()),
+                    app_name: family_name(&family),
+                }
+            })
+            .collect()
+    }
+
+    fn error(&self, message: String) -> ThemeError {
+        ThemeError {
+            message,
+            app_name: self.config_key.clone(),
+        }
+    }
+
+    /// Splits a theme value into the UI theme id and optional editor color scheme
+    pub fn parse_theme(theme: &str) -> (&str, Option<&str>) {
+        match theme.split_once('|') {
+            Some((laf, scheme)) => (laf.trim(), Some(scheme.trim())),
+            None => (theme.trim(), None),
+        }
+    }
+
+    /// Sets the UI theme in the contents of `laf.xml`
+    ///
+    /// OS appearance sync is turned off if it is on, since it would override the theme.
+    pub fn update_laf(contents: &str, theme_id: &str) -> String {
+        let component = &laf_path()[..2];
+        let contents = match xml::get_attribute(contents, component, "autodetect") {
+            Some(autodetect) if autodetect == "true" => {
+                xml::set_attribute(contents, component, "autodetect", "false")
+            }
+            _ => contents.to_string(),
+        };
+        xml::set_attribute(&contents, &laf_path(), "themeId", theme_id)
+    }
+
+    /// Sets the editor color scheme in the contents of `colors.scheme.xml`
+    pub fn update_scheme(contents: &str, scheme: &str) -> String {
+        xml::set_attribute(contents, &scheme_path(), "name", scheme)
+    }
+
+    fn read_file(&self, path: &Path) -> Result<String, ThemeError> {
+        if !path.exists() {
+            return Ok(String::new());
+        }
+        std::fs::read_to_string(path)
+            .map_err(|e| self.error(format!("Failed to read {}: {}", path.display(), e)))
+    }
+
+    fn edit_file(&self, path: &Path, edit: impl Fn(&str) -> String) -> Result<(), ThemeError> {
+        let updated = edit(&self.read_file(path)?);
+        write_atomic(path, updated)
+            .map_err(|e| self.error(format!("Failed to write {}: {}", path.display(), e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for JetBrainsAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let (theme_id, scheme) = Self::parse_theme(theme);
+        if theme_id.is_empty() {
+            return Err(self.error("Theme value is missing the UI theme id".to_string()));
+        }
+
+        for product_dir in &self.product_dirs {
+            let options = product_dir.join("options");
+            self.edit_file(&options.join(LAF_FILE), |contents| {
+                Self::update_laf(contents, theme_id)
+            })?;
+            if let Some(scheme) = scheme {
+                self.edit_file(&options.join(SCHEME_FILE), |contents| {
+                    Self::update_scheme(contents, scheme)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let Some(latest) = self.product_dirs.last() else {
+            return Ok("default".to_string());
+        };
+        let options = latest.join("options");
+
+        let laf = self.read_file(&options.join(LAF_FILE))?;
+        let scheme = self.read_file(&options.join(SCHEME_FILE))?;
+        // Absent settings mean the IDE's own defaults
+        let theme_id = xml::get_attribute(&laf, &laf_path(), "themeId")
+            .unwrap_or_else(|| "ExperimentalDark".to_string());
+        let scheme = xml::get_attribute(&scheme, &scheme_path(), "name")
+            .unwrap_or_else(|| "Dark".to_string());
+        Ok(format!("{}|{}", theme_id, scheme))
+    }
+
+    async fn is_available(&self) -> bool {
+        !self.product_dirs.is_empty()
+    }
+
+    fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    fn config_key(&self) -> &str {
+        &self.config_key
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "ExperimentalDark|Dark".to_string());
+        themes.insert("light".to_string(), "ExperimentalLight|Light".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        self.product_dirs
+            .iter()
+            .flat_map(|dir| {
+                [
+                    dir.join("options").join(LAF_FILE),
+                    dir.join("options").join(SCHEME_FILE),
+                ]
+            })
+            .collect()
+    }
+
+    fn needs_restart(&self) -> bool {
+        true
+    }
+}
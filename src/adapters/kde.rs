@@ -0,0 +1,222 @@
+//! Adapter for KDE Plasma / Qt theme synchronization via color schemes in `kdeglobals`.
+//!
+//! A theme value is the name of a Plasma color scheme, i.e. a `<Name>.colors` file.
+//! `plasma-apply-colorscheme` is used when installed, since it also tells running apps
+//! to repaint. Otherwise the scheme's color groups are copied into `kdeglobals` and
+//! `[General] ColorScheme` is set, which apps pick up when they next start.
+//!
+//! # Configuration Locations
+//! - Linux: `~/.config/kdeglobals`
+//! - Color schemes: `~/.local/share/color-schemes/` and `/usr/share/color-schemes/`
+//!
+//! # Default Themes
+//! - dark: BreezeDark
+//! - light: BreezeLight
+
+use crate::adapter::{unsupported_method, ApplyMethod, ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::ini;
+use crate::runner::{CommandRunner, SystemRunner};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const SYSTEM_SCHEMES_DIR: &str = "/usr/share/color-schemes";
+
+pub struct KdeAdapter {
+    kdeglobals_path: PathBuf,
+    themes_dir: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for KdeAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KdeAdapter {
+    pub fn new() -> Self {
+        let kdeglobals_path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("kdeglobals");
+
+        Self::with_path(kdeglobals_path)
+    }
+
+    /// Creates an adapter that copies color schemes into the `kdeglobals` at `kdeglobals_path`
+    pub fn with_path(kdeglobals_path: PathBuf) -> Self {
+        let themes_dir = dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("color-schemes");
+
+        Self {
+            kdeglobals_path,
+            themes_dir,
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Looks for `<scheme>.colors` files in `themes_dir` before `/usr/share/color-schemes`
+    pub fn with_themes_dir(self, themes_dir: PathBuf) -> Self {
+        Self { themes_dir, ..self }
+    }
+
+    /// Applies schemes with `plasma-apply-colorscheme` through `runner`, so running apps repaint
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    fn error(message: String) -> ThemeError {
+        ThemeError {
+            message,
+            app_name: "kde".to_string(),
+        }
+    }
+
+    /// Returns the `.colors` file defining `scheme`, preferring user-installed ones
+    fn scheme_file(&self, scheme: &str) -> Option<PathBuf> {
+        let file_name = format!("{}.colors", scheme);
+        [self.themes_dir.clone(), PathBuf::from(SYSTEM_SCHEMES_DIR)]
+            .into_iter()
+            .map(|dir| dir.join(&file_name))
+            .find(|path| path.is_file())
+    }
+
+    /// Copies the groups of a color scheme into the contents of `kdeglobals`
+    ///
+    /// Every group except `[General]` (which only names the scheme) is copied key by
+    /// key, leaving other settings in `kdeglobals` untouched. `[Colors:*]` groups are
+    /// emptied first, as `plasma-apply-colorscheme` does, so colors the previous scheme
+    /// set and this one lacks don't linger.
+    pub fn update_kdeglobals(contents: &str, scheme: &str, colors: &str) -> String {
+        let mut contents = contents.to_string();
+        for section in ini::sections(colors) {
+            if section == "General" {
+                continue;
+            }
+            if section.starts_with("Colors:") {
+                contents = ini::clear_section(&contents, &section);
+            }
+            for (key, value) in ini::entries(colors, &section) {
+                contents = ini::set(&contents, &section, &key, &value);
+            }
+        }
+        ini::set(&contents, "General", "ColorScheme", scheme)
+    }
+
+    fn apply_to_file(&self, scheme: &str) -> Result<(), ThemeError> {
+        let scheme_path = self
+            .scheme_file(scheme)
+            .ok_or_else(|| Self::error(format!("Color scheme file {}.colors not found", scheme)))?;
+        let colors = std::fs::read_to_string(&scheme_path)
+            .map_err(|e| Self::error(format!("Failed to read {}: {}", scheme_path.display(), e)))?;
+
+        let contents = if self.kdeglobals_path.exists() {
+            std::fs::read_to_string(&self.kdeglobals_path)
+                .map_err(|e| Self::error(format!("Failed to read kdeglobals: {}", e)))?
+        } else {
+            String::new()
+        };
+        write_atomic(
+            &self.kdeglobals_path,
+            Self::update_kdeglobals(&contents, scheme, &colors),
+        )
+        .map_err(|e| Self::error(format!("Failed to write kdeglobals: {}", e)))
+    }
+
+    async fn apply_with_command(&self, scheme: &str) -> Result<(), ThemeError> {
+        let output = self
+            .runner
+            .run("plasma-apply-colorscheme", &[scheme])
+            .await
+            .map_err(|e| Self::error(format!("Failed to run plasma-apply-colorscheme: {}", e)))?;
+
+        if output.success {
+            Ok(())
+        } else {
+            Err(Self::error(format!(
+                "plasma-apply-colorscheme failed: {}",
+                output.stderr.trim()
+            )))
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for KdeAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        self.set_theme_with(theme, ApplyMethod::Auto).await
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        if !self.kdeglobals_path.exists() {
+            return Ok("BreezeLight".to_string()); // Plasma default
+        }
+
+        let contents = std::fs::read_to_string(&self.kdeglobals_path)
+            .map_err(|e| Self::error(format!("Failed to read kdeglobals: {}", e)))?;
+        Ok(ini::get(&contents, "General", "ColorScheme")
+            .unwrap_or_else(|| "BreezeLight".to_string()))
+    }
+
+    async fn is_available(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            return false;
+        }
+
+        let plasma_paths = ["/usr/bin/plasmashell", "/usr/bin/plasma-apply-colorscheme"];
+        plasma_paths.iter().any(|path| PathBuf::from(path).exists())
+            || self.kdeglobals_path.exists()
+    }
+
+    fn app_name(&self) -> &str {
+        "KDE Plasma"
+    }
+
+    fn config_key(&self) -> &str {
+        "kde"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "BreezeDark".to_string());
+        themes.insert("light".to_string(), "BreezeLight".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.kdeglobals_path.clone()]
+    }
+
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::Command, ApplyMethod::File]
+    }
+
+    /// Returns the scheme `kdeglobals` names, to hand back to `plasma-apply-colorscheme`
+    async fn capture_state(&self) -> Result<Option<String>, ThemeError> {
+        self.get_theme().await.map(Some)
+    }
+
+    /// Reapplies the captured scheme so running apps repaint; `kdeglobals` itself is
+    /// restored from its snapshot afterwards, so a missing command is not an error
+    async fn restore_state(&self, scheme: &str) -> Result<(), ThemeError> {
+        let _ = self.apply_with_command(scheme).await;
+        Ok(())
+    }
+
+    async fn set_theme_with(&self, theme: &str, method: ApplyMethod) -> Result<(), ThemeError> {
+        match method {
+            ApplyMethod::File => self.apply_to_file(theme),
+            ApplyMethod::Command => self.apply_with_command(theme).await,
+            // Editing kdeglobals first would make plasma-apply-colorscheme see the scheme
+            // as already applied and skip notifying running apps, so it goes first
+            ApplyMethod::Auto => match self.apply_with_command(theme).await {
+                Ok(()) => Ok(()),
+                Err(_) => self.apply_to_file(theme),
+            },
+            method @ ApplyMethod::Ipc => Err(unsupported_method(self, method)),
+        }
+    }
+}
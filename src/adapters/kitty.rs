@@ -0,0 +1,350 @@
+//! Adapter for kitty theme synchronization via an included theme file and remote control.
+//!
+//! Mirrors `kitten themes`: the chosen theme is copied to `current-theme.conf` and
+//! included from a marked block in `kitty.conf`. When `allow_remote_control` and
+//! `listen_on` are configured, running windows are recolored with
+//! `kitty @ set-colors --all --configured`.
+//!
+//! # Configuration Locations
+//! - macOS: `~/.config/kitty/kitty.conf`
+//! - Linux: `~/.config/kitty/kitty.conf`
+//!
+//! # Default Themes
+//! - dark: Tokyo Night
+//! - light: Catppuccin-Latte
+
+use crate::adapter::{unsupported_method, ApplyMethod, ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::config::expand_home;
+use crate::runner::{is_socket, CommandRunner, SystemRunner};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const THEME_BLOCK_START: &str = "# BEGIN_KITTY_THEME";
+const THEME_BLOCK_END: &str = "# END_KITTY_THEME";
+const CURRENT_THEME_FILE: &str = "current-theme.conf";
+
+pub struct KittyAdapter {
+    config_path: PathBuf,
+    themes_dir: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for KittyAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KittyAdapter {
+    pub fn new() -> Self {
+        Self::with_path(Self::config_dir().join("kitty.conf"))
+    }
+
+    /// Creates an adapter that maintains the theme include block of the `kitty.conf` at `config_path`
+    pub fn with_path(config_path: PathBuf) -> Self {
+        Self {
+            config_path,
+            themes_dir: Self::config_dir().join("themes"),
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Looks for `<theme>.conf` files in `themes_dir` instead of `~/.config/kitty/themes`
+    pub fn with_themes_dir(self, themes_dir: PathBuf) -> Self {
+        Self { themes_dir, ..self }
+    }
+
+    /// Pushes colors to running instances with `kitty @ set-colors` through `runner`
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    // kitty reads ~/.config on macOS too, not ~/Library
+    fn config_dir() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config")
+            .join("kitty")
+    }
+
+    fn current_theme_path(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(CURRENT_THEME_FILE)
+    }
+
+    fn read_config(&self) -> Result<String, ThemeError> {
+        if !self.config_path.exists() {
+            return Ok(String::new());
+        }
+
+        std::fs::read_to_string(&self.config_path).map_err(|e| ThemeError {
+            message: format!("Failed to read kitty.conf: {}", e),
+            app_name: "kitty".to_string(),
+        })
+    }
+
+    fn write_file(path: &Path, contents: &str) -> Result<(), ThemeError> {
+        write_atomic(path, contents).map_err(|e| ThemeError {
+            message: format!("Failed to write {}: {}", path.display(), e),
+            app_name: "kitty".to_string(),
+        })
+    }
+
+    /// Returns the value of the last `key value` line for `key`
+    fn setting<'a>(config: &'a str, key: &str) -> Option<&'a str> {
+        config
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                let (name, value) = line.split_once(char::is_whitespace)?;
+                (name == key).then(|| value.trim())
+            })
+            .next_back()
+    }
+
+    /// Replaces (or appends) the themesync/kitten theme block that includes `current-theme.conf`
+    pub fn update_theme_block(config: &str, theme: &str) -> String {
+        let block = format!(
+            "{}\n# {}\ninclude {}\n{}\n",
+            THEME_BLOCK_START, theme, CURRENT_THEME_FILE, THEME_BLOCK_END
+        );
+
+        let start = config.find(THEME_BLOCK_START);
+        let end = start.and_then(|start| {
+            config[start..]
+                .find(THEME_BLOCK_END)
+                .map(|offset| start + offset + THEME_BLOCK_END.len())
+        });
+
+        match (start, end) {
+            (Some(start), Some(end)) => {
+                // Swallow the block's own line break; `block` ends with one
+                let end = if config[end..].starts_with('\n') {
+                    end + 1
+                } else {
+                    end
+                };
+                format!("{}{}{}", &config[..start], block, &config[end..])
+            }
+            _ => {
+                let mut updated = config.to_string();
+                if !updated.is_empty() && !updated.ends_with('\n') {
+                    updated.push('\n');
+                }
+                if !updated.is_empty() {
+                    updated.push('\n');
+                }
+                updated.push_str(&block);
+                updated
+            }
+        }
+    }
+
+    /// Returns the theme name recorded in the theme block
+    pub fn extract_theme_from_config(config: &str) -> Option<String> {
+        let start = config.find(THEME_BLOCK_START)? + THEME_BLOCK_START.len();
+        config[start..]
+            .lines()
+            .map(str::trim)
+            .take_while(|line| *line != THEME_BLOCK_END)
+            .find_map(|line| line.strip_prefix('#'))
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Returns the `kitty @ --to` addresses of running instances, if remote control is enabled
+    ///
+    /// kitty appends `-<pid>` to unix socket paths from `listen_on`, so every matching
+    /// socket next to the configured path is a separate instance.
+    pub fn remote_addresses(config: &str) -> Vec<String> {
+        let remote_control = Self::setting(config, "allow_remote_control").unwrap_or("no");
+        if matches!(remote_control, "no" | "n" | "false") {
+            return Vec::new();
+        }
+        let Some(listen_on) = Self::setting(config, "listen_on") else {
+            return Vec::new();
+        };
+
+        let path = match listen_on.strip_prefix("unix:") {
+            Some(path) if !path.starts_with('@') => expand_home(Path::new(path)),
+            // Abstract sockets and TCP addresses can't be enumerated; use them as-is
+            _ => return vec![listen_on.to_string()],
+        };
+
+        let Some(dir) = path.parent() else {
+            return Vec::new();
+        };
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (prefix, suffix) = match file_name.split_once("{kitty_pid}") {
+            Some((prefix, suffix)) => (prefix.to_string(), suffix.to_string()),
+            None => (format!("{}-", file_name), String::new()),
+        };
+
+        let mut sockets: Vec<PathBuf> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|candidate| {
+                let name = candidate
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let pid = name
+                    .strip_prefix(&prefix)
+                    .and_then(|rest| rest.strip_suffix(suffix.as_str()));
+                (name == file_name
+                    || pid.is_some_and(|pid| pid.chars().all(|c| c.is_ascii_digit())))
+                    && is_socket(candidate)
+            })
+            .collect();
+        sockets.sort();
+
+        sockets
+            .into_iter()
+            .map(|socket| format!("unix:{}", socket.display()))
+            .collect()
+    }
+
+    fn theme_file(&self, theme: &str) -> Result<PathBuf, ThemeError> {
+        let theme_file = self.themes_dir.join(format!("{}.conf", theme));
+        if theme_file.is_file() {
+            Ok(theme_file)
+        } else {
+            Err(ThemeError {
+                message: format!("Theme file {} not found", theme_file.display()),
+                app_name: "kitty".to_string(),
+            })
+        }
+    }
+
+    fn apply_to_file(&self, theme: &str, theme_file: &Path) -> Result<(), ThemeError> {
+        let colors = std::fs::read_to_string(theme_file).map_err(|e| ThemeError {
+            message: format!("Failed to read {}: {}", theme_file.display(), e),
+            app_name: "kitty".to_string(),
+        })?;
+        Self::write_file(&self.current_theme_path(), &colors)?;
+
+        let config = self.read_config()?;
+        Self::write_file(&self.config_path, &Self::update_theme_block(&config, theme))
+    }
+
+    /// Pushes the theme's colors into every running instance; returns how many were updated
+    async fn apply_live(&self, theme_file: &Path) -> Result<usize, ThemeError> {
+        let config = self.read_config()?;
+        let theme_file = theme_file.to_string_lossy();
+        let mut updated = 0;
+        let mut last_error = None;
+
+        for address in Self::remote_addresses(&config) {
+            let args = [
+                "@",
+                "--to",
+                address.as_str(),
+                "set-colors",
+                "--all",
+                "--configured",
+                theme_file.as_ref(),
+            ];
+            match self.runner.run("kitty", &args).await {
+                Ok(output) if output.success => updated += 1,
+                Ok(output) => last_error = Some(output.stderr.trim().to_string()),
+                Err(e) => last_error = Some(e.to_string()),
+            }
+        }
+
+        match (updated, last_error) {
+            (0, Some(error)) => Err(ThemeError {
+                message: format!("kitty @ set-colors failed: {}", error),
+                app_name: "kitty".to_string(),
+            }),
+            _ => Ok(updated),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for KittyAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        self.set_theme_with(theme, ApplyMethod::Auto).await
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let config = self.read_config()?;
+
+        match Self::extract_theme_from_config(&config) {
+            Some(theme) => Ok(theme),
+            None => Ok("default".to_string()), // kitty built-in colors
+        }
+    }
+
+    async fn is_available(&self) -> bool {
+        let kitty_paths = [
+            "/Applications/kitty.app/Contents/MacOS/kitty",
+            "/usr/local/bin/kitty",
+            "/usr/bin/kitty",
+            "/opt/homebrew/bin/kitty",
+        ];
+
+        if kitty_paths.iter().any(|path| PathBuf::from(path).exists()) {
+            return true;
+        }
+
+        self.runner
+            .run("kitty", &["--version"])
+            .await
+            .is_ok_and(|output| output.success)
+    }
+
+    fn app_name(&self) -> &str {
+        "kitty"
+    }
+
+    fn config_key(&self) -> &str {
+        "kitty"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "Tokyo Night".to_string());
+        themes.insert("light".to_string(), "Catppuccin-Latte".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.config_path.clone(), self.current_theme_path()]
+    }
+
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::File, ApplyMethod::Ipc]
+    }
+
+    async fn set_theme_with(&self, theme: &str, method: ApplyMethod) -> Result<(), ThemeError> {
+        let theme_file = self.theme_file(theme)?;
+
+        match method {
+            ApplyMethod::File => self.apply_to_file(theme, &theme_file),
+            ApplyMethod::Ipc => match self.apply_live(&theme_file).await? {
+                0 => Err(ThemeError {
+                    message: "No running kitty instance with remote control enabled".to_string(),
+                    app_name: "kitty".to_string(),
+                }),
+                _ => Ok(()),
+            },
+            ApplyMethod::Auto => {
+                self.apply_to_file(theme, &theme_file)?;
+                let _ = self.apply_live(&theme_file).await;
+                Ok(())
+            }
+            method @ ApplyMethod::Command => Err(unsupported_method(self, method)),
+        }
+    }
+}
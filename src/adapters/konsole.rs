@@ -0,0 +1,157 @@
+//! Adapter for Konsole theme synchronization via `ColorScheme=` in profile files.
+//!
+//! A theme value is the name of a Konsole color scheme (a `<Name>.colorscheme` file),
+//! set under `[Appearance]` in every profile. Konsole loads profiles on startup, so
+//! open windows keep their colors until restarted.
+//!
+//! # Configuration Locations
+//! - Linux: `~/.local/share/konsole/*.profile`
+//!
+//! # Default Themes
+//! - dark: Breeze
+//! - light: BlackOnWhite
+
+use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::ini;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const SECTION: &str = "Appearance";
+
+pub struct KonsoleAdapter {
+    profiles_dir: PathBuf,
+    konsolerc_path: PathBuf,
+}
+
+impl Default for KonsoleAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KonsoleAdapter {
+    pub fn new() -> Self {
+        let profiles_dir = dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("konsole");
+
+        Self::with_path(profiles_dir)
+    }
+
+    /// Creates an adapter that sets `ColorScheme=` in the `*.profile` files of `profiles_dir`
+    pub fn with_path(profiles_dir: PathBuf) -> Self {
+        let konsolerc_path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("konsolerc");
+
+        Self {
+            profiles_dir,
+            konsolerc_path,
+        }
+    }
+
+    /// Reads the default profile name from `konsolerc_path` instead of `~/.config/konsolerc`
+    pub fn with_konsolerc(self, konsolerc_path: PathBuf) -> Self {
+        Self {
+            konsolerc_path,
+            ..self
+        }
+    }
+
+    fn error(message: String) -> ThemeError {
+        ThemeError {
+            message,
+            app_name: "konsole".to_string(),
+        }
+    }
+
+    /// Returns every `.profile` file in the profiles directory
+    fn profiles(&self) -> Vec<PathBuf> {
+        let mut profiles: Vec<PathBuf> = std::fs::read_dir(&self.profiles_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "profile"))
+            .collect();
+        profiles.sort();
+        profiles
+    }
+
+    /// Returns the profile new Konsole windows open with, falling back to the first one
+    fn default_profile(&self) -> Option<PathBuf> {
+        let configured = std::fs::read_to_string(&self.konsolerc_path)
+            .ok()
+            .and_then(|konsolerc| ini::get(&konsolerc, "Desktop Entry", "DefaultProfile"))
+            .map(|name| self.profiles_dir.join(name))
+            .filter(|path| path.is_file());
+
+        configured.or_else(|| self.profiles().into_iter().next())
+    }
+
+    fn read_profile(path: &Path) -> Result<String, ThemeError> {
+        std::fs::read_to_string(path)
+            .map_err(|e| Self::error(format!("Failed to read {}: {}", path.display(), e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for KonsoleAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let profiles = self.profiles();
+        if profiles.is_empty() {
+            return Err(Self::error(format!(
+                "No Konsole profiles found in {}; create one under Settings > Manage Profiles",
+                self.profiles_dir.display()
+            )));
+        }
+
+        for path in profiles {
+            let updated = ini::set(&Self::read_profile(&path)?, SECTION, "ColorScheme", theme);
+            write_atomic(&path, updated)
+                .map_err(|e| Self::error(format!("Failed to write {}: {}", path.display(), e)))?;
+        }
+        Ok(())
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let Some(path) = self.default_profile() else {
+            return Ok("Breeze".to_string()); // Konsole built-in profile
+        };
+
+        let profile = Self::read_profile(&path)?;
+        Ok(ini::get(&profile, SECTION, "ColorScheme").unwrap_or_else(|| "Breeze".to_string()))
+    }
+
+    async fn is_available(&self) -> bool {
+        let konsole_paths = ["/usr/bin/konsole", "/usr/local/bin/konsole"];
+        konsole_paths
+            .iter()
+            .any(|path| PathBuf::from(path).exists())
+    }
+
+    fn app_name(&self) -> &str {
+        "Konsole"
+    }
+
+    fn config_key(&self) -> &str {
+        "konsole"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "Breeze".to_string());
+        themes.insert("light".to_string(), "BlackOnWhite".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        self.profiles()
+    }
+
+    fn needs_restart(&self) -> bool {
+        true
+    }
+}
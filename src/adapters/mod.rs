@@ -1,12 +1,45 @@
+pub mod alacritty;
+pub mod emacs;
+pub mod foot;
 pub mod ghostty;
+pub mod gnome_terminal;
+pub mod gtk;
 pub mod helix;
+pub mod jetbrains;
+pub mod kde;
+pub mod kitty;
+pub mod konsole;
+pub mod neovim;
+pub mod sublime;
+pub mod tmux;
 pub mod vscode;
+pub mod wezterm;
+pub mod windows_terminal;
+pub mod zed;
 
+pub use alacritty::AlacrittyAdapter;
+pub use emacs::EmacsAdapter;
+pub use foot::FootAdapter;
 pub use ghostty::GhosttyAdapter;
+pub use gnome_terminal::GnomeTerminalAdapter;
+pub use gtk::{GtkAdapter, GtkTheme};
 pub use helix::HelixAdapter;
-pub use vscode::VsCodeAdapter;
+pub use jetbrains::JetBrainsAdapter;
+pub use kde::KdeAdapter;
+pub use kitty::KittyAdapter;
+pub use konsole::KonsoleAdapter;
+pub use neovim::NeovimAdapter;
+pub use sublime::{SublimeAdapter, SublimeProduct};
+pub use tmux::TmuxAdapter;
+pub use vscode::{VsCodeAdapter, VsCodeVariant};
+pub use wezterm::WezTermAdapter;
+pub use windows_terminal::WindowsTerminalAdapter;
+pub use zed::ZedAdapter;
 
 use crate::adapter::ThemeAdapter;
+use crate::config::{AppConfig, Config};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 pub struct AdapterRegistry {
@@ -20,12 +53,118 @@ impl Default for AdapterRegistry {
 }
 
 impl AdapterRegistry {
+    /// Creates a registry with every adapter at its default config location
     pub fn new() -> Self {
-        let adapters: Vec<Arc<dyn ThemeAdapter>> = vec![
-            Arc::new(VsCodeAdapter::new()),
-            Arc::new(GhosttyAdapter::new()),
-            Arc::new(HelixAdapter::new()),
+        Self::with_app_configs(&HashMap::new())
+    }
+
+    /// Creates a registry of the apps enabled in `config`, honoring their `path` overrides
+    pub fn from_config(config: &Config) -> Self {
+        let mut registry = Self::with_app_configs(&config.apps);
+        registry
+            .adapters
+            .retain(|adapter| config.is_app_enabled(adapter.config_key()));
+        registry
+    }
+
+    /// Creates a registry of every app, enabled or not, honoring the `path` and
+    /// `themes_dir` overrides in `apps`
+    pub fn with_app_configs(apps: &HashMap<String, AppConfig>) -> Self {
+        let path = |key: &str| -> Option<PathBuf> { apps.get(key)?.resolved_path() };
+        let themes_dir = |key: &str| -> Option<PathBuf> { apps.get(key)?.resolved_themes_dir() };
+
+        let alacritty =
+            path("alacritty").map_or_else(AlacrittyAdapter::new, AlacrittyAdapter::with_path);
+        let alacritty = match themes_dir("alacritty") {
+            Some(dir) => alacritty.with_themes_dir(dir),
+            None => alacritty,
+        };
+
+        let kitty = path("kitty").map_or_else(KittyAdapter::new, KittyAdapter::with_path);
+        let kitty = match themes_dir("kitty") {
+            Some(dir) => kitty.with_themes_dir(dir),
+            None => kitty,
+        };
+
+        let tmux = path("tmux").map_or_else(TmuxAdapter::new, TmuxAdapter::with_path);
+        let tmux = match themes_dir("tmux") {
+            Some(dir) => tmux.with_themes_dir(dir),
+            None => tmux,
+        };
+
+        let kde = path("kde").map_or_else(KdeAdapter::new, KdeAdapter::with_path);
+        let kde = match themes_dir("kde") {
+            Some(dir) => kde.with_themes_dir(dir),
+            None => kde,
+        };
+
+        let foot = path("foot").map_or_else(FootAdapter::new, FootAdapter::with_path);
+        let foot = match themes_dir("foot") {
+            Some(dir) => foot.with_themes_dir(dir),
+            None => foot,
+        };
+
+        // GNOME Terminal keeps its profiles in dconf, so there is no file to point it at
+        if let Some(ignored) = path("gnome-terminal") {
+            eprintln!(
+                "Warning: ignoring path {} for gnome-terminal, which is configured through dconf",
+                ignored.display()
+            );
+        }
+        let gnome_terminal = match themes_dir("gnome-terminal") {
+            Some(dir) => GnomeTerminalAdapter::new().with_themes_dir(dir),
+            None => GnomeTerminalAdapter::new(),
+        };
+
+        // Each VS Code variant, followed by its profiles
+        let relocate = |adapter: VsCodeAdapter| match path(adapter.config_key()) {
+            Some(settings_path) => adapter.with_settings_path(settings_path),
+            None => adapter,
+        };
+        let mut adapters: Vec<Arc<dyn ThemeAdapter>> = Vec::new();
+        for variant in VsCodeVariant::ALL {
+            let vscode = relocate(VsCodeAdapter::for_variant(variant));
+            let profiles = vscode.profiles();
+            adapters.push(Arc::new(vscode));
+            for profile in profiles {
+                adapters.push(Arc::new(relocate(profile)));
+            }
+        }
+
+        let other_adapters: Vec<Arc<dyn ThemeAdapter>> = vec![
+            Arc::new(path("ghostty").map_or_else(GhosttyAdapter::new, GhosttyAdapter::with_path)),
+            Arc::new(path("helix").map_or_else(HelixAdapter::new, HelixAdapter::with_path)),
+            Arc::new(alacritty),
+            Arc::new(kitty),
+            Arc::new(path("neovim").map_or_else(NeovimAdapter::new, NeovimAdapter::with_path)),
+            Arc::new(tmux),
+            Arc::new(path("wezterm").map_or_else(WezTermAdapter::new, WezTermAdapter::with_path)),
+            Arc::new(path("zed").map_or_else(ZedAdapter::new, ZedAdapter::with_path)),
+            Arc::new(path("emacs").map_or_else(EmacsAdapter::new, EmacsAdapter::with_path)),
+            Arc::new(path("gtk").map_or_else(GtkAdapter::new, GtkAdapter::with_path)),
+            Arc::new(kde),
+            Arc::new(path("windows-terminal").map_or_else(
+                WindowsTerminalAdapter::new,
+                WindowsTerminalAdapter::with_path,
+            )),
+            Arc::new(foot),
+            Arc::new(path("konsole").map_or_else(KonsoleAdapter::new, KonsoleAdapter::with_path)),
+            Arc::new(gnome_terminal),
         ];
+        adapters.extend(other_adapters);
+
+        for product in [SublimeProduct::Text, SublimeProduct::Merge] {
+            adapters.push(Arc::new(match path(product.config_key()) {
+                Some(settings_path) => SublimeAdapter::with_path(product, settings_path),
+                None => SublimeAdapter::new(product),
+            }));
+        }
+
+        // One adapter per JetBrains product family that has been run on this machine
+        let jetbrains_root = path("jetbrains").unwrap_or_else(JetBrainsAdapter::default_root);
+        for jetbrains in JetBrainsAdapter::discover(&jetbrains_root) {
+            adapters.push(Arc::new(jetbrains));
+        }
 
         Self { adapters }
     }
@@ -0,0 +1,407 @@
+//! Adapter for Neovim theme synchronization via a generated Lua module and msgpack-RPC.
+//!
+//! themesync owns `lua/themesync.lua`, which sets `background` and runs `:colorscheme`;
+//! the user requires it once from `init.lua`. Running instances are found through their
+//! server sockets and switched immediately over msgpack-RPC.
+//!
+//! Theme values are a colorscheme name, optionally prefixed with the background to use:
+//! `dark:tokyonight`, `light:tokyonight-day`.
+//!
+//! # Configuration Locations
+//! - macOS: `~/.config/nvim/lua/themesync.lua`
+//! - Linux: `~/.config/nvim/lua/themesync.lua`
+//!
+//! # Default Themes
+//! - dark: dark:tokyonight
+//! - light: light:tokyonight-day
+
+use crate::adapter::{unsupported_method, ApplyMethod, ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::lua;
+use crate::runner::is_socket;
+use anyhow::Result;
+use rmpv::Value;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+
+const MODULE_NAME: &str = "themesync";
+
+/// How long a single instance may take to answer before it is skipped
+const RPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Message id of the only request sent on each connection
+const REQUEST_ID: u32 = 1;
+
+pub struct NeovimAdapter {
+    module_path: PathBuf,
+    runtime_dir: PathBuf,
+}
+
+impl Default for NeovimAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NeovimAdapter {
+    pub fn new() -> Self {
+        // Neovim uses ~/.config on macOS too, not ~/Library
+        let module_path = dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config")
+            .join("nvim")
+            .join("lua")
+            .join(format!("{}.lua", MODULE_NAME));
+
+        Self::with_path(module_path)
+    }
+
+    /// Creates an adapter that writes the generated `lua/themesync.lua` to `module_path`
+    pub fn with_path(module_path: PathBuf) -> Self {
+        Self {
+            module_path,
+            runtime_dir: dirs::runtime_dir().unwrap_or_else(std::env::temp_dir),
+        }
+    }
+
+    /// Looks for server sockets in `runtime_dir` instead of `$XDG_RUNTIME_DIR` (or the temp dir)
+    pub fn with_runtime_dir(self, runtime_dir: PathBuf) -> Self {
+        Self {
+            runtime_dir,
+            ..self
+        }
+    }
+
+    /// Splits a theme value into its optional background and the colorscheme name
+    pub fn parse_theme(theme: &str) -> (Option<&str>, &str) {
+        for background in ["dark", "light"] {
+            if let Some(name) = theme
+                .strip_prefix(background)
+                .and_then(|rest| rest.strip_prefix(':'))
+            {
+                return (Some(background), name);
+            }
+        }
+        (None, theme)
+    }
+
+    /// Checks that the colorscheme is a plain name, since it is sent as an Ex command
+    /// where `|` would start another command
+    fn validate_theme(theme: &str) -> Result<(), ThemeError> {
+        let (_, colorscheme) = Self::parse_theme(theme);
+        let is_name = !colorscheme.is_empty()
+            && colorscheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c));
+        if is_name {
+            Ok(())
+        } else {
+            Err(ThemeError {
+                message: format!("'{}' is not a valid Neovim colorscheme name", colorscheme),
+                app_name: "neovim".to_string(),
+            })
+        }
+    }
+
+    /// Returns the contents of the generated module for `theme`
+    pub fn render_module(theme: &str) -> String {
+        let (background, colorscheme) = Self::parse_theme(theme);
+
+        let mut module = format!(
+            "-- Generated by themesync; changes are overwritten on the next theme switch.\n\
+             -- Load it from init.lua, after your colorscheme plugins, with: require(\"{}\")\n",
+            MODULE_NAME
+        );
+        if let Some(background) = background {
+            module.push_str(&format!("vim.o.background = {}\n", lua::quote(background)));
+        }
+        module.push_str(&format!(
+            "vim.cmd.colorscheme({})\n",
+            lua::quote(colorscheme)
+        ));
+        module
+    }
+
+    /// Returns the theme value written by `render_module`
+    pub fn extract_theme_from_module(module: &str) -> Option<String> {
+        let value_after = |prefix: &str| {
+            module
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix(prefix))
+                .filter_map(lua::unquote)
+                .next_back()
+        };
+
+        let colorscheme = value_after("vim.cmd.colorscheme(")?;
+        match value_after("vim.o.background =") {
+            Some(background) => Some(format!("{}:{}", background, colorscheme)),
+            None => Some(colorscheme),
+        }
+    }
+
+    /// Returns the server sockets of running instances under `runtime_dir`
+    ///
+    /// Linux puts them directly in `$XDG_RUNTIME_DIR` (`nvim.<pid>.0`); macOS nests them
+    /// as `$TMPDIR/nvim.<user>/<random>/nvim.<pid>.0`.
+    pub fn discover_sockets(runtime_dir: &Path) -> Vec<PathBuf> {
+        let mut sockets = Vec::new();
+        Self::collect_sockets(runtime_dir, 0, &mut sockets);
+        sockets.sort();
+        sockets
+    }
+
+    fn collect_sockets(dir: &Path, depth: usize, sockets: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let is_nvim = entry.file_name().to_string_lossy().starts_with("nvim.");
+
+            if is_socket(&path) {
+                if is_nvim {
+                    sockets.push(path);
+                }
+            } else if path.is_dir() && ((depth == 0 && is_nvim) || depth == 1) {
+                Self::collect_sockets(&path, depth + 1, sockets);
+            }
+        }
+    }
+
+    fn read_module(&self) -> Result<Option<String>, ThemeError> {
+        if !self.module_path.exists() {
+            return Ok(None);
+        }
+
+        std::fs::read_to_string(&self.module_path)
+            .map(Some)
+            .map_err(|e| ThemeError {
+                message: format!("Failed to read {}: {}", self.module_path.display(), e),
+                app_name: "neovim".to_string(),
+            })
+    }
+
+    fn apply_to_file(&self, theme: &str) -> Result<(), ThemeError> {
+        write_atomic(&self.module_path, Self::render_module(theme)).map_err(|e| ThemeError {
+            message: format!("Failed to write {}: {}", self.module_path.display(), e),
+            app_name: "neovim".to_string(),
+        })
+    }
+
+    /// Switches every running instance to `theme`; returns how many were updated
+    async fn apply_live(&self, theme: &str) -> Result<usize, ThemeError> {
+        let (background, colorscheme) = Self::parse_theme(theme);
+        let command = match background {
+            Some(background) => format!(
+                "set background={} | colorscheme {}",
+                background, colorscheme
+            ),
+            None => format!("colorscheme {}", colorscheme),
+        };
+
+        let mut updated = 0;
+        let mut last_error = None;
+        for socket in Self::discover_sockets(&self.runtime_dir) {
+            let request = rpc_request(&socket, "nvim_command", vec![command.as_str().into()]);
+            match tokio::time::timeout(RPC_TIMEOUT, request).await {
+                Ok(Ok(_)) => updated += 1,
+                Ok(Err(e)) => last_error = Some(format!("{}: {}", socket.display(), e)),
+                Err(_) => last_error = Some(format!("{}: no response", socket.display())),
+            }
+        }
+
+        match (updated, last_error) {
+            (0, Some(error)) => Err(ThemeError {
+                message: format!("Failed to switch running Neovim: {}", error),
+                app_name: "neovim".to_string(),
+            }),
+            _ => Ok(updated),
+        }
+    }
+}
+
+/// Sends one msgpack-RPC request to the Neovim server at `socket` and returns its result
+#[cfg(unix)]
+async fn rpc_request(socket: &Path, method: &str, params: Vec<Value>) -> io::Result<Value> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::UnixStream::connect(socket).await?;
+    let request = Value::Array(vec![
+        0.into(),
+        REQUEST_ID.into(),
+        method.into(),
+        Value::Array(params),
+    ]);
+    let mut encoded = Vec::new();
+    rmpv::encode::write_value(&mut encoded, &request).map_err(io::Error::other)?;
+    stream.write_all(&encoded).await?;
+
+    let mut received = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        // Skip notifications and requests from the server until our response arrives
+        let mut pending = received.as_slice();
+        loop {
+            let message = match rmpv::decode::read_value(&mut pending) {
+                Ok(message) => message,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+            if let Some(result) = rpc_response(&message) {
+                return result;
+            }
+            received.drain(..received.len() - pending.len());
+            pending = received.as_slice();
+        }
+
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed before a response",
+            ));
+        }
+        received.extend_from_slice(&chunk[..read]);
+    }
+}
+
+#[cfg(not(unix))]
+async fn rpc_request(_socket: &Path, _method: &str, _params: Vec<Value>) -> io::Result<Value> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Neovim sockets are only supported on Unix",
+    ))
+}
+
+/// Returns the outcome carried by `message` if it is the response to our request
+fn rpc_response(message: &Value) -> Option<io::Result<Value>> {
+    let [kind, id, error, result] = message.as_array()?.as_slice() else {
+        return None;
+    };
+    if kind.as_u64() != Some(1) || id.as_u64() != Some(REQUEST_ID.into()) {
+        return None;
+    }
+
+    if error.is_nil() {
+        return Some(Ok(result.clone()));
+    }
+    // Neovim reports errors as [type, message]
+    let message = error
+        .as_array()
+        .and_then(|error| error.get(1))
+        .and_then(Value::as_str)
+        .map_or_else(|| error.to_string(), str::to_string);
+    Some(Err(io::Error::other(message)))
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for NeovimAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        self.set_theme_with(theme, ApplyMethod::Auto).await
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let Some(module) = self.read_module()? else {
+            return Ok("default".to_string()); // Neovim built-in colorscheme
+        };
+
+        Self::extract_theme_from_module(&module).ok_or_else(|| ThemeError {
+            message: format!("{} does not set a colorscheme", self.module_path.display()),
+            app_name: "neovim".to_string(),
+        })
+    }
+
+    async fn is_available(&self) -> bool {
+        let nvim_paths = [
+            "/usr/local/bin/nvim",
+            "/usr/bin/nvim",
+            "/opt/homebrew/bin/nvim",
+        ];
+
+        if nvim_paths.iter().any(|path| PathBuf::from(path).exists()) {
+            return true;
+        }
+
+        Command::new("nvim")
+            .arg("--version")
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    fn app_name(&self) -> &str {
+        "Neovim"
+    }
+
+    fn config_key(&self) -> &str {
+        "neovim"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "dark:tokyonight".to_string());
+        themes.insert("light".to_string(), "light:tokyonight-day".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.module_path.clone()]
+    }
+
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::File, ApplyMethod::Ipc]
+    }
+
+    async fn set_theme_with(&self, theme: &str, method: ApplyMethod) -> Result<(), ThemeError> {
+        Self::validate_theme(theme)?;
+
+        match method {
+            ApplyMethod::File => self.apply_to_file(theme),
+            ApplyMethod::Ipc => match self.apply_live(theme).await? {
+                0 => Err(ThemeError {
+                    message: "No running Neovim instance found".to_string(),
+                    app_name: "neovim".to_string(),
+                }),
+                _ => Ok(()),
+            },
+            ApplyMethod::Auto => {
+                self.apply_to_file(theme)?;
+                let _ = self.apply_live(theme).await;
+                Ok(())
+            }
+            method @ ApplyMethod::Command => Err(unsupported_method(self, method)),
+        }
+    }
+
+    async fn doctor(&self) -> Vec<String> {
+        // The module lives in <config>/lua/, next to init.lua
+        let Some(config_dir) = self.module_path.parent().and_then(Path::parent) else {
+            return Vec::new();
+        };
+        let hint = format!(
+            "add require(\"{}\") after your colorscheme plugins are loaded",
+            MODULE_NAME
+        );
+
+        let init_files = [config_dir.join("init.lua"), config_dir.join("init.vim")];
+        let Some(init_file) = init_files.iter().find(|path| path.is_file()) else {
+            return vec![format!(
+                "No init.lua found in {}; {}",
+                config_dir.display(),
+                hint
+            )];
+        };
+
+        match std::fs::read_to_string(init_file) {
+            Ok(init) if lua::requires_module(&init, MODULE_NAME) => Vec::new(),
+            Ok(_) => vec![format!(
+                "{} does not load the generated theme; {}",
+                init_file.display(),
+                hint
+            )],
+            Err(e) => vec![format!("Failed to read {}: {}", init_file.display(), e)],
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! Adapter for Sublime Text and Sublime Merge theme synchronization via
+//! `Preferences.sublime-settings`.
+//!
+//! A theme value names the files to use, separated by `|`: UI themes end in
+//! `.sublime-theme`, anything else is a color scheme (Sublime Text only), e.g.
+//! `Mariana.sublime-color-scheme|Default Dark.sublime-theme`. A `light:...,dark:...`
+//! pair sets the setting to `auto` and fills in its `light_`/`dark_` variants.
+//!
+//! # Configuration Locations
+//! - macOS: `~/Library/Application Support/Sublime Text/Packages/User/Preferences.sublime-settings`
+//! - Linux: `~/.config/sublime-text/Packages/User/Preferences.sublime-settings`
+//!
+//! Sublime Merge uses the same layout under `Sublime Merge` / `sublime-merge`.
+//!
+//! # Default Themes
+//! - dark: Mariana.sublime-color-scheme|Default Dark.sublime-theme (Merge: Merge Dark.sublime-theme)
+//! - light: Breakers.sublime-color-scheme|Default.sublime-theme (Merge: Merge Light.sublime-theme)
+
+use crate::adapter::{ThemeAdapter, ThemeError, ThemePair};
+use crate::appearance::{detect_appearance, Appearance};
+use crate::atomic_write::write_atomic;
+use crate::jsonc;
+use crate::runner::{CommandRunner, SystemRunner};
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A setting that is either a file name or `auto`, deferring to `light_<key>`/`dark_<key>`
+pub struct VariantSetting {
+    pub key: &'static str,
+    /// Value Sublime uses when the setting is absent
+    pub default: &'static str,
+    pub default_light: &'static str,
+    pub default_dark: &'static str,
+}
+
+impl VariantSetting {
+    fn variant_key(&self, appearance: Appearance) -> String {
+        format!("{}_{}", appearance.as_str(), self.key)
+    }
+
+    fn variant_default(&self, appearance: Appearance) -> &'static str {
+        match appearance {
+            Appearance::Light => self.default_light,
+            Appearance::Dark => self.default_dark,
+        }
+    }
+}
+
+const COLOR_SCHEME: VariantSetting = VariantSetting {
+    key: "color_scheme",
+    default: "Mariana.sublime-color-scheme",
+    default_light: "Breakers.sublime-color-scheme",
+    default_dark: "Mariana.sublime-color-scheme",
+};
+
+const TEXT_THEME: VariantSetting = VariantSetting {
+    key: "theme",
+    default: "auto",
+    default_light: "Default.sublime-theme",
+    default_dark: "Default Dark.sublime-theme",
+};
+
+const MERGE_THEME: VariantSetting = VariantSetting {
+    key: "theme",
+    default: "auto",
+    default_light: "Merge Light.sublime-theme",
+    default_dark: "Merge Dark.sublime-theme",
+};
+
+/// The Sublime HQ application an adapter edits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SublimeProduct {
+    Text,
+    Merge,
+}
+
+impl SublimeProduct {
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            SublimeProduct::Text => "sublime-text",
+            SublimeProduct::Merge => "sublime-merge",
+        }
+    }
+
+    pub fn app_name(&self) -> &'static str {
+        match self {
+            SublimeProduct::Text => "Sublime Text",
+            SublimeProduct::Merge => "Sublime Merge",
+        }
+    }
+
+    /// Returns the settings that a theme value can set, in `|`-separated output order
+    pub fn settings(&self) -> &'static [VariantSetting] {
+        match self {
+            SublimeProduct::Text => &[COLOR_SCHEME, TEXT_THEME],
+            SublimeProduct::Merge => &[MERGE_THEME],
+        }
+    }
+
+    fn data_dir_name(&self) -> &'static str {
+        match (self, cfg!(target_os = "macos")) {
+            (SublimeProduct::Text, true) => "Sublime Text",
+            (SublimeProduct::Text, false) => "sublime-text",
+            (SublimeProduct::Merge, true) => "Sublime Merge",
+            (SublimeProduct::Merge, false) => "sublime-merge",
+        }
+    }
+
+    fn install_paths(&self) -> &'static [&'static str] {
+        match self {
+            SublimeProduct::Text => &[
+                "/Applications/Sublime Text.app",
+                "/opt/sublime_text/sublime_text",
+                "/usr/local/bin/subl",
+                "/usr/bin/subl",
+            ],
+            SublimeProduct::Merge => &[
+                "/Applications/Sublime Merge.app",
+                "/opt/sublime_merge/sublime_merge",
+                "/usr/local/bin/smerge",
+                "/usr/bin/smerge",
+            ],
+        }
+    }
+}
+
+pub struct SublimeAdapter {
+    product: SublimeProduct,
+    settings_path: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl SublimeAdapter {
+    /// Creates an adapter for `product` at its default settings location
+    pub fn new(product: SublimeProduct) -> Self {
+        let settings_path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(product.data_dir_name())
+            .join("Packages")
+            .join("User")
+            .join("Preferences.sublime-settings");
+
+        Self::with_path(product, settings_path)
+    }
+
+    /// Creates an adapter for `product` that edits the `Preferences.sublime-settings` at `settings_path`
+    pub fn with_path(product: SublimeProduct, settings_path: PathBuf) -> Self {
+        Self {
+            product,
+            settings_path,
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Asks the OS for its appearance through `runner` when a setting is `"auto"`
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    fn error(&self, message: String) -> ThemeError {
+        ThemeError {
+            message,
+            app_name: self.product.config_key().to_string(),
+        }
+    }
+
+    fn read_contents(&self) -> Result<String, ThemeError> {
+        if !self.settings_path.exists() {
+            return Ok(String::new());
+        }
+
+        std::fs::read_to_string(&self.settings_path).map_err(|e| {
+            self.error(format!(
+                "Failed to read Preferences.sublime-settings: {}",
+                e
+            ))
+        })
+    }
+
+    fn parse_settings(&self, contents: &str) -> Result<Map<String, Value>, ThemeError> {
+        match jsonc::parse(contents) {
+            Ok(Value::Object(map)) => Ok(map),
+            Ok(_) => {
+                Err(self.error("Preferences.sublime-settings is not a JSON object".to_string()))
+            }
+            Err(e) => Err(self.error(format!(
+                "Failed to parse Preferences.sublime-settings: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Assigns each `|`-separated file in `value` to the setting it belongs to
+    fn classify<'a>(&self, value: &'a str) -> Vec<(&'static VariantSetting, &'a str)> {
+        value
+            .split('|')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                let setting = match self.product {
+                    SublimeProduct::Text if !name.ends_with(".sublime-theme") => &COLOR_SCHEME,
+                    SublimeProduct::Text => &TEXT_THEME,
+                    SublimeProduct::Merge => &MERGE_THEME,
+                };
+                (setting, name)
+            })
+            .collect()
+    }
+
+    fn current_value<'a>(settings: &'a Map<String, Value>, setting: &VariantSetting) -> &'a str {
+        settings
+            .get(setting.key)
+            .and_then(Value::as_str)
+            .unwrap_or(setting.default)
+    }
+
+    /// Whether any setting `theme` touches defers to the system appearance
+    fn follows_system(&self, settings: &Map<String, Value>, theme: Option<&str>) -> bool {
+        let mut touched: Vec<&VariantSetting> = self.product.settings().iter().collect();
+        if let Some(theme) = theme {
+            touched = self
+                .classify(theme)
+                .into_iter()
+                .map(|(setting, _)| setting)
+                .collect();
+        }
+        touched
+            .iter()
+            .any(|setting| Self::current_value(settings, setting) == "auto")
+    }
+
+    /// Writes `theme` into the settings in `contents`, keeping comments intact
+    ///
+    /// For a plain value, a setting that is `auto` gets the variant for `appearance`
+    /// changed instead, so it keeps following the system.
+    pub fn update_theme(
+        &self,
+        contents: &str,
+        theme: &str,
+        appearance: Option<Appearance>,
+    ) -> Result<String, ThemeError> {
+        let settings = self.parse_settings(contents)?;
+        let mut contents = contents.to_string();
+        let mut set = |key: &str, value: &str| -> Result<(), ThemeError> {
+            contents = jsonc::set_value(&contents, &[key], &Value::String(value.to_string()))
+                .map_err(|e| {
+                    self.error(format!(
+                        "Failed to update Preferences.sublime-settings: {}",
+                        e
+                    ))
+                })?;
+            Ok(())
+        };
+
+        match ThemePair::parse(theme) {
+            Some(pair) => {
+                for (variant, names) in [
+                    (Appearance::Light, pair.light.as_str()),
+                    (Appearance::Dark, pair.dark.as_str()),
+                ] {
+                    for (setting, name) in self.classify(names) {
+                        set(setting.key, "auto")?;
+                        set(&setting.variant_key(variant), name)?;
+                    }
+                }
+            }
+            None => {
+                for (setting, name) in self.classify(theme) {
+                    if Self::current_value(&settings, setting) == "auto" {
+                        let variant = appearance.unwrap_or(Appearance::Dark);
+                        set(&setting.variant_key(variant), name)?;
+                    } else {
+                        set(setting.key, name)?;
+                    }
+                }
+            }
+        }
+
+        Ok(contents)
+    }
+
+    /// Returns the files Sublime is currently using, joined with `|`
+    pub fn effective_theme(
+        &self,
+        settings: &Map<String, Value>,
+        appearance: Option<Appearance>,
+    ) -> String {
+        let files: Vec<&str> = self
+            .product
+            .settings()
+            .iter()
+            .map(|setting| match Self::current_value(settings, setting) {
+                "auto" => {
+                    let variant = appearance.unwrap_or(Appearance::Dark);
+                    settings
+                        .get(&setting.variant_key(variant))
+                        .and_then(Value::as_str)
+                        .unwrap_or(setting.variant_default(variant))
+                }
+                value => value,
+            })
+            .collect();
+        files.join("|")
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for SublimeAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let contents = self.read_contents()?;
+        let settings = self.parse_settings(&contents)?;
+        let appearance = if self.follows_system(&settings, Some(theme)) {
+            detect_appearance(self.runner.as_ref()).await
+        } else {
+            None
+        };
+
+        let updated = self.update_theme(&contents, theme, appearance)?;
+        write_atomic(&self.settings_path, updated).map_err(|e| {
+            self.error(format!(
+                "Failed to write Preferences.sublime-settings: {}",
+                e
+            ))
+        })
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let settings = self.parse_settings(&self.read_contents()?)?;
+        let appearance = if self.follows_system(&settings, None) {
+            detect_appearance(self.runner.as_ref()).await
+        } else {
+            None
+        };
+
+        Ok(self.effective_theme(&settings, appearance))
+    }
+
+    async fn is_available(&self) -> bool {
+        self.product
+            .install_paths()
+            .iter()
+            .any(|path| PathBuf::from(path).exists())
+    }
+
+    fn app_name(&self) -> &str {
+        self.product.app_name()
+    }
+
+    fn config_key(&self) -> &str {
+        self.product.config_key()
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let join = |appearance: Appearance| {
+            let files: Vec<&str> = self
+                .product
+                .settings()
+                .iter()
+                .map(|setting| setting.variant_default(appearance))
+                .collect();
+            files.join("|")
+        };
+
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), join(Appearance::Dark));
+        themes.insert("light".to_string(), join(Appearance::Light));
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.settings_path.clone()]
+    }
+}
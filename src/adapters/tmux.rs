@@ -0,0 +1,320 @@
+//! Adapter for tmux theme synchronization via a generated, sourced config file.
+//!
+//! Each theme is a snippet of tmux commands (status colors, pane borders, ...) in the
+//! themes directory. Switching copies the snippet into `themesync.conf`, which the
+//! user sources once from `tmux.conf`, and sources it into every running server.
+//! The default themes are built in, so they work without any snippet files; a file of
+//! the same name in the themes directory takes precedence.
+//!
+//! # Configuration Locations
+//! - macOS: `~/.config/tmux/themesync.conf`
+//! - Linux: `~/.config/tmux/themesync.conf`
+//!
+//! # Default Themes
+//! - dark: tokyonight
+//! - light: catppuccin-latte
+
+use crate::adapter::{unsupported_method, ApplyMethod, ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::runner::{is_socket, CommandRunner, SystemRunner};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const THEME_MARKER: &str = "# theme: ";
+
+/// Snippets for the default themes, used when the themes directory has no such file
+const BUILTIN_THEMES: &[(&str, &str)] = &[
+    (
+        "tokyonight",
+        "set -g status-style 'bg=#16161e,fg=#a9b1d6'\n\
+         set -g message-style 'bg=#292e42,fg=#c0caf5'\n\
+         set -g mode-style 'bg=#283457,fg=#c0caf5'\n\
+         set -g pane-border-style 'fg=#3b4261'\n\
+         set -g pane-active-border-style 'fg=#7aa2f7'\n\
+         set -g window-status-style 'fg=#565f89'\n\
+         set -g window-status-current-style 'bg=#7aa2f7,fg=#16161e,bold'\n",
+    ),
+    (
+        "catppuccin-latte",
+        "set -g status-style 'bg=#e6e9ef,fg=#4c4f69'\n\
+         set -g message-style 'bg=#ccd0da,fg=#4c4f69'\n\
+         set -g mode-style 'bg=#bcc0cc,fg=#4c4f69'\n\
+         set -g pane-border-style 'fg=#bcc0cc'\n\
+         set -g pane-active-border-style 'fg=#1e66f5'\n\
+         set -g window-status-style 'fg=#8c8fa1'\n\
+         set -g window-status-current-style 'bg=#1e66f5,fg=#eff1f5,bold'\n",
+    ),
+];
+
+pub struct TmuxAdapter {
+    managed_path: PathBuf,
+    themes_dir: PathBuf,
+    socket_dir: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for TmuxAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TmuxAdapter {
+    pub fn new() -> Self {
+        Self::with_path(Self::config_dir().join("themesync.conf"))
+    }
+
+    /// Creates an adapter that writes the generated `themesync.conf` to `managed_path`
+    pub fn with_path(managed_path: PathBuf) -> Self {
+        Self {
+            managed_path,
+            themes_dir: Self::config_dir().join("themes"),
+            socket_dir: Self::default_socket_dir(),
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Looks for `<theme>.conf` snippets in `themes_dir` instead of `~/.config/tmux/themes`
+    pub fn with_themes_dir(self, themes_dir: PathBuf) -> Self {
+        Self { themes_dir, ..self }
+    }
+
+    /// Looks for server sockets in `socket_dir` instead of `$TMUX_TMPDIR/tmux-$UID`
+    pub fn with_socket_dir(self, socket_dir: PathBuf) -> Self {
+        Self { socket_dir, ..self }
+    }
+
+    /// Sources the generated file into running servers with `tmux` through `runner`
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    fn config_dir() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config")
+            .join("tmux")
+    }
+
+    /// Returns the directory tmux creates server sockets in, `/tmp/tmux-$UID` by default
+    fn default_socket_dir() -> PathBuf {
+        let tmp_dir = std::env::var_os("TMUX_TMPDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/tmp"));
+
+        #[cfg(unix)]
+        // SAFETY: getuid has no preconditions and cannot fail
+        let uid = unsafe { libc::getuid() };
+        #[cfg(not(unix))]
+        let uid = 0;
+
+        tmp_dir.join(format!("tmux-{}", uid))
+    }
+
+    /// Returns the server sockets in `socket_dir`, one per named server (`-L`)
+    pub fn discover_sockets(socket_dir: &Path) -> Vec<PathBuf> {
+        let mut sockets: Vec<PathBuf> = std::fs::read_dir(socket_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_socket(path))
+            .collect();
+        sockets.sort();
+        sockets
+    }
+
+    /// Returns the contents of the generated file for `theme`
+    pub fn render_managed_file(theme: &str, snippet: &str) -> String {
+        let mut contents = format!(
+            "# Generated by themesync; changes are overwritten on the next theme switch.\n\
+             # Load it from tmux.conf with: source-file ~/.config/tmux/themesync.conf\n\
+             {}{}\n",
+            THEME_MARKER, theme
+        );
+        contents.push_str(snippet);
+        if !snippet.ends_with('\n') {
+            contents.push('\n');
+        }
+        contents
+    }
+
+    /// Returns the theme name recorded in the generated file
+    pub fn extract_theme_from_managed_file(contents: &str) -> Option<String> {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(THEME_MARKER))
+            .map(|theme| theme.trim().to_string())
+    }
+
+    fn apply_to_file(&self, theme: &str) -> Result<(), ThemeError> {
+        let snippet_path = self.themes_dir.join(format!("{}.conf", theme));
+        let snippet = if snippet_path.is_file() {
+            std::fs::read_to_string(&snippet_path).map_err(|e| ThemeError {
+                message: format!("Failed to read {}: {}", snippet_path.display(), e),
+                app_name: "tmux".to_string(),
+            })?
+        } else if let Some((_, snippet)) = BUILTIN_THEMES.iter().find(|(name, _)| *name == theme) {
+            snippet.to_string()
+        } else {
+            return Err(ThemeError {
+                message: format!("Theme file {} not found", snippet_path.display()),
+                app_name: "tmux".to_string(),
+            });
+        };
+        write_atomic(
+            &self.managed_path,
+            Self::render_managed_file(theme, &snippet),
+        )
+        .map_err(|e| ThemeError {
+            message: format!("Failed to write {}: {}", self.managed_path.display(), e),
+            app_name: "tmux".to_string(),
+        })
+    }
+
+    /// Sources the generated file into every running server; returns how many were updated
+    async fn apply_live(&self) -> Result<usize, ThemeError> {
+        let managed_path = self.managed_path.to_string_lossy();
+        let mut updated = 0;
+        let mut last_error = None;
+
+        for socket in Self::discover_sockets(&self.socket_dir) {
+            let socket = socket.to_string_lossy();
+            let args = ["-S", socket.as_ref(), "source-file", managed_path.as_ref()];
+            match self.runner.run("tmux", &args).await {
+                Ok(output) if output.success => updated += 1,
+                // Sockets outlive servers that crashed; those are simply not running
+                Ok(output) if output.stderr.contains("no server running") => {}
+                Ok(output) => last_error = Some(output.stderr.trim().to_string()),
+                Err(e) => last_error = Some(e.to_string()),
+            }
+        }
+
+        match (updated, last_error) {
+            (0, Some(error)) => Err(ThemeError {
+                message: format!("tmux source-file failed: {}", error),
+                app_name: "tmux".to_string(),
+            }),
+            _ => Ok(updated),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for TmuxAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        self.set_theme_with(theme, ApplyMethod::Auto).await
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        if !self.managed_path.exists() {
+            return Ok("default".to_string()); // tmux built-in colors
+        }
+
+        let contents = std::fs::read_to_string(&self.managed_path).map_err(|e| ThemeError {
+            message: format!("Failed to read {}: {}", self.managed_path.display(), e),
+            app_name: "tmux".to_string(),
+        })?;
+        Ok(Self::extract_theme_from_managed_file(&contents)
+            .unwrap_or_else(|| "default".to_string()))
+    }
+
+    async fn is_available(&self) -> bool {
+        let tmux_paths = [
+            "/usr/local/bin/tmux",
+            "/usr/bin/tmux",
+            "/opt/homebrew/bin/tmux",
+        ];
+
+        if tmux_paths.iter().any(|path| PathBuf::from(path).exists()) {
+            return true;
+        }
+
+        self.runner
+            .run("tmux", &["-V"])
+            .await
+            .is_ok_and(|output| output.success)
+    }
+
+    fn app_name(&self) -> &str {
+        "tmux"
+    }
+
+    fn config_key(&self) -> &str {
+        "tmux"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "tokyonight".to_string());
+        themes.insert("light".to_string(), "catppuccin-latte".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.managed_path.clone()]
+    }
+
+    fn supported_methods(&self) -> Vec<ApplyMethod> {
+        vec![ApplyMethod::File, ApplyMethod::Ipc]
+    }
+
+    async fn set_theme_with(&self, theme: &str, method: ApplyMethod) -> Result<(), ThemeError> {
+        match method {
+            ApplyMethod::File => self.apply_to_file(theme),
+            ApplyMethod::Ipc => {
+                self.apply_to_file(theme)?;
+                match self.apply_live().await? {
+                    0 => Err(ThemeError {
+                        message: "No running tmux server found".to_string(),
+                        app_name: "tmux".to_string(),
+                    }),
+                    _ => Ok(()),
+                }
+            }
+            // Servers that can't be reached are fine: they pick the file up from tmux.conf
+            // when they start again
+            ApplyMethod::Auto => {
+                self.apply_to_file(theme)?;
+                let _ = self.apply_live().await;
+                Ok(())
+            }
+            method @ ApplyMethod::Command => Err(unsupported_method(self, method)),
+        }
+    }
+
+    async fn doctor(&self) -> Vec<String> {
+        let mut config_paths = Vec::new();
+        if let Some(home) = dirs::home_dir() {
+            config_paths.push(home.join(".tmux.conf"));
+        }
+        if let Some(dir) = self.managed_path.parent() {
+            config_paths.push(dir.join("tmux.conf"));
+        }
+        let hint = format!("add source-file {}", self.managed_path.display());
+
+        let Some(config_path) = config_paths.iter().find(|path| path.is_file()) else {
+            return vec![format!("No tmux.conf found; {}", hint)];
+        };
+
+        let sources_managed_file = |config: &str| {
+            config.lines().any(|line| {
+                let line = line.trim();
+                !line.starts_with('#')
+                    && line.starts_with("source")
+                    && line.contains("themesync.conf")
+            })
+        };
+        match std::fs::read_to_string(config_path) {
+            Ok(config) if sources_managed_file(&config) => Vec::new(),
+            Ok(_) => vec![format!(
+                "{} does not load the generated theme; {}",
+                config_path.display(),
+                hint
+            )],
+            Err(e) => vec![format!("Failed to read {}: {}", config_path.display(), e)],
+        }
+    }
+}
@@ -1,11 +1,141 @@
-use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::adapter::{ThemeAdapter, ThemeError, ThemePair};
+use crate::appearance::{detect_appearance, Appearance};
+use crate::atomic_write::write_atomic;
+use crate::jsonc;
+use crate::runner::{CommandRunner, SystemRunner};
 use anyhow::Result;
 use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+const COLOR_THEME: &str = "workbench.colorTheme";
+const PREFERRED_DARK: &str = "workbench.preferredDarkColorTheme";
+const PREFERRED_LIGHT: &str = "workbench.preferredLightColorTheme";
+const AUTO_DETECT: &str = "window.autoDetectColorScheme";
+
+/// An editor built on VS Code that keeps its settings in the same `User/settings.json` layout
+///
+/// Each variant has its own config key, so it can carry its own theme mappings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsCodeVariant {
+    Code,
+    Insiders,
+    CodeOss,
+    VsCodium,
+    Cursor,
+    /// VS Code installed from Flathub, which keeps its config inside the sandbox
+    Flatpak,
+}
+
+impl VsCodeVariant {
+    pub const ALL: [VsCodeVariant; 6] = [
+        VsCodeVariant::Code,
+        VsCodeVariant::Insiders,
+        VsCodeVariant::CodeOss,
+        VsCodeVariant::VsCodium,
+        VsCodeVariant::Cursor,
+        VsCodeVariant::Flatpak,
+    ];
+
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            VsCodeVariant::Code => "vscode",
+            VsCodeVariant::Insiders => "vscode-insiders",
+            VsCodeVariant::CodeOss => "code-oss",
+            VsCodeVariant::VsCodium => "vscodium",
+            VsCodeVariant::Cursor => "cursor",
+            VsCodeVariant::Flatpak => "vscode-flatpak",
+        }
+    }
+
+    pub fn app_name(&self) -> &'static str {
+        match self {
+            VsCodeVariant::Code => "VS Code",
+            VsCodeVariant::Insiders => "VS Code Insiders",
+            VsCodeVariant::CodeOss => "Code - OSS",
+            VsCodeVariant::VsCodium => "VSCodium",
+            VsCodeVariant::Cursor => "Cursor",
+            VsCodeVariant::Flatpak => "VS Code (Flatpak)",
+        }
+    }
+
+    /// Returns the default `User` directory holding `settings.json`
+    fn user_dir(&self) -> PathBuf {
+        let config_dir = || dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+        let home_dir = || dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+
+        let app_dir = match self {
+            VsCodeVariant::Code => config_dir().join("Code"),
+            VsCodeVariant::Insiders => config_dir().join("Code - Insiders"),
+            VsCodeVariant::CodeOss => config_dir().join("Code - OSS"),
+            VsCodeVariant::VsCodium => config_dir().join("VSCodium"),
+            VsCodeVariant::Cursor => config_dir().join("Cursor"),
+            VsCodeVariant::Flatpak => home_dir()
+                .join(".var/app/com.visualstudio.code/config")
+                .join("Code"),
+        };
+        app_dir.join("User")
+    }
+
+    /// Returns the command that succeeds when the variant is installed, looked up on `PATH`
+    fn cli_probe(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            VsCodeVariant::Code => ("code", &["--version"]),
+            VsCodeVariant::Insiders => ("code-insiders", &["--version"]),
+            VsCodeVariant::CodeOss => ("code-oss", &["--version"]),
+            VsCodeVariant::VsCodium => ("codium", &["--version"]),
+            VsCodeVariant::Cursor => ("cursor", &["--version"]),
+            VsCodeVariant::Flatpak => ("flatpak", &["info", "com.visualstudio.code"]),
+        }
+    }
+
+    /// Returns paths whose existence shows the variant is installed
+    fn install_paths(&self) -> Vec<PathBuf> {
+        let paths: &[&str] = match self {
+            VsCodeVariant::Code => &[
+                "/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code",
+                "/usr/local/bin/code",
+                "/usr/bin/code",
+            ],
+            VsCodeVariant::Insiders => &[
+                "/Applications/Visual Studio Code - Insiders.app",
+                "/usr/local/bin/code-insiders",
+                "/usr/bin/code-insiders",
+            ],
+            VsCodeVariant::CodeOss => &["/Applications/Code - OSS.app", "/usr/bin/code-oss"],
+            VsCodeVariant::VsCodium => &[
+                "/Applications/VSCodium.app",
+                "/usr/local/bin/codium",
+                "/usr/bin/codium",
+                "/opt/homebrew/bin/codium",
+            ],
+            VsCodeVariant::Cursor => &[
+                "/Applications/Cursor.app",
+                "/usr/local/bin/cursor",
+                "/usr/bin/cursor",
+            ],
+            VsCodeVariant::Flatpak => &["/var/lib/flatpak/app/com.visualstudio.code"],
+        };
+
+        let mut paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        if *self == VsCodeVariant::Flatpak {
+            if let Some(data_dir) = dirs::data_dir() {
+                paths.push(data_dir.join("flatpak/app/com.visualstudio.code"));
+            }
+        }
+        paths
+    }
+}
 
 pub struct VsCodeAdapter {
     settings_path: PathBuf,
+    /// Set when `settings_path` was configured explicitly, which counts as the app being installed
+    path_override: bool,
+    variant: VsCodeVariant,
+    config_key: String,
+    app_name: String,
+    runner: Arc<dyn CommandRunner>,
 }
 
 impl Default for VsCodeAdapter {
@@ -16,105 +146,292 @@ impl Default for VsCodeAdapter {
 
 impl VsCodeAdapter {
     pub fn new() -> Self {
-        let settings_path = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("Code")
-            .join("User")
-            .join("settings.json");
+        Self::for_variant(VsCodeVariant::Code)
+    }
 
-        Self { settings_path }
+    /// Creates a VS Code adapter that edits the user `settings.json` at `settings_path`
+    pub fn with_path(settings_path: PathBuf) -> Self {
+        Self::new().with_settings_path(settings_path)
     }
 
-    fn read_settings(&self) -> Result<Map<String, Value>, ThemeError> {
+    /// Creates an adapter for `variant` at its default settings location
+    pub fn for_variant(variant: VsCodeVariant) -> Self {
+        Self {
+            settings_path: variant.user_dir().join("settings.json"),
+            path_override: false,
+            variant,
+            config_key: variant.config_key().to_string(),
+            app_name: variant.app_name().to_string(),
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Edits `settings.json` at `settings_path`; profiles are then looked up next to it
+    pub fn with_settings_path(self, settings_path: PathBuf) -> Self {
+        Self {
+            settings_path,
+            path_override: true,
+            ..self
+        }
+    }
+
+    /// Runs the variant's CLI through `runner`, both to detect the install and to ask the OS
+    /// for its appearance when `window.autoDetectColorScheme` is on
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    /// Returns an adapter per user profile that has its own settings
+    ///
+    /// Profiles live in `User/profiles/<id>/settings.json`; their names come from
+    /// `User/globalStorage/storage.json`. Each gets the config key
+    /// `<variant key>-profile-<name>`, e.g. `vscode-profile-work`. Names that reduce to
+    /// the same key get their profile id appended, and names without any letters or
+    /// digits are replaced by the id.
+    pub fn profiles(&self) -> Vec<VsCodeAdapter> {
+        let Some(user_dir) = self.settings_path.parent() else {
+            return Vec::new();
+        };
+
+        let storage = std::fs::read_to_string(user_dir.join("globalStorage/storage.json"))
+            .ok()
+            .and_then(|contents| jsonc::parse(&contents).ok());
+        let known_profiles = storage
+            .as_ref()
+            .and_then(|storage| storage.get("userDataProfiles"))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut ids: Vec<String> = std::fs::read_dir(user_dir.join("profiles"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        ids.sort();
+
+        let named: Vec<(String, String, String)> = ids
+            .into_iter()
+            .filter_map(|id| {
+                let profile = known_profiles
+                    .iter()
+                    .find(|profile| profile.get("location").and_then(Value::as_str) == Some(&id));
+                // Profiles can share the default profile's settings instead of having their own
+                let shares_settings = profile
+                    .and_then(|profile| profile.pointer("/useDefaultFlags/settings"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                if shares_settings {
+                    return None;
+                }
+
+                let name = profile
+                    .and_then(|profile| profile.get("name"))
+                    .and_then(Value::as_str)
+                    .unwrap_or(&id)
+                    .to_string();
+                let slug = match slugify(&name) {
+                    slug if slug.is_empty() => slugify(&id),
+                    slug => slug,
+                };
+                if slug.is_empty() {
+                    return None;
+                }
+                Some((id, name, slug))
+            })
+            .collect();
+
+        named
+            .iter()
+            .map(|(id, name, slug)| {
+                let shared = named.iter().filter(|(_, _, other)| other == slug).count() > 1;
+                let key_suffix = if shared {
+                    format!("{}-{}", slug, slugify(id))
+                } else {
+                    slug.clone()
+                };
+                VsCodeAdapter {
+                    settings_path: user_dir.join("profiles").join(id).join("settings.json"),
+                    path_override: false,
+                    variant: self.variant,
+                    config_key: format!("{}-profile-{}", self.config_key, key_suffix),
+                    app_name: format!("{} ({})", self.app_name, name),
+                    runner: self.runner.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn read_contents(&self) -> Result<String, ThemeError> {
         if !self.settings_path.exists() {
-            return Ok(Map::new());
+            return Ok(String::new());
         }
 
-        let contents = std::fs::read_to_string(&self.settings_path).map_err(|e| ThemeError {
+        std::fs::read_to_string(&self.settings_path).map_err(|e| ThemeError {
             message: format!("Failed to read settings.json: {}", e),
-            app_name: "vscode".to_string(),
-        })?;
+            app_name: self.config_key.clone(),
+        })
+    }
 
-        if contents.trim().is_empty() {
-            return Ok(Map::new());
-        }
+    fn read_settings(&self) -> Result<Map<String, Value>, ThemeError> {
+        let contents = self.read_contents()?;
 
-        let value: Value = serde_json::from_str(&contents).map_err(|e| ThemeError {
+        let value = jsonc::parse(&contents).map_err(|e| ThemeError {
             message: format!("Failed to parse settings.json: {}", e),
-            app_name: "vscode".to_string(),
+            app_name: self.config_key.clone(),
         })?;
 
         match value {
             Value::Object(map) => Ok(map),
             _ => Err(ThemeError {
                 message: "settings.json is not a JSON object".to_string(),
-                app_name: "vscode".to_string(),
+                app_name: self.config_key.clone(),
             }),
         }
     }
 
-    fn write_settings(&self, settings: &Map<String, Value>) -> Result<(), ThemeError> {
-        if let Some(parent) = self.settings_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| ThemeError {
-                message: format!("Failed to create settings directory: {}", e),
-                app_name: "vscode".to_string(),
-            })?;
-        }
-
-        let contents = serde_json::to_string_pretty(settings).map_err(|e| ThemeError {
-            message: format!("Failed to serialize settings: {}", e),
-            app_name: "vscode".to_string(),
-        })?;
-
-        std::fs::write(&self.settings_path, contents).map_err(|e| ThemeError {
+    fn write_contents(&self, contents: &str) -> Result<(), ThemeError> {
+        write_atomic(&self.settings_path, contents).map_err(|e| ThemeError {
             message: format!("Failed to write settings.json: {}", e),
-            app_name: "vscode".to_string(),
+            app_name: self.config_key.clone(),
         })?;
 
         Ok(())
     }
+
+    /// Sets `key` in settings.json, leaving comments and all other settings untouched
+    pub fn update_setting(contents: &str, key: &str, value: Value) -> Result<String, ThemeError> {
+        jsonc::set_value(contents, &[key], &value).map_err(|e| ThemeError {
+            message: format!("Failed to update settings.json: {}", e),
+            app_name: "vscode".to_string(),
+        })
+    }
+
+    fn auto_detects(settings: &Map<String, Value>) -> bool {
+        settings.get(AUTO_DETECT).and_then(Value::as_bool) == Some(true)
+    }
+
+    /// Returns the preferred theme setting VS Code uses for `appearance` under auto-detect
+    fn preferred_key(appearance: Option<Appearance>) -> &'static str {
+        match appearance {
+            Some(Appearance::Light) => PREFERRED_LIGHT,
+            // VS Code assumes a dark OS when it can't tell
+            Some(Appearance::Dark) | None => PREFERRED_DARK,
+        }
+    }
+
+    /// Writes `theme` into the theme settings of `contents`
+    ///
+    /// A `light:X,dark:Y` pair installs both preferred themes and turns on
+    /// `window.autoDetectColorScheme`. A plain name sets `workbench.colorTheme`, or,
+    /// when auto-detect is on, the preferred theme for the current `appearance`, as
+    /// VS Code's own theme picker does.
+    pub fn update_theme(
+        contents: &str,
+        theme: &str,
+        appearance: Option<Appearance>,
+    ) -> Result<String, ThemeError> {
+        if let Some(pair) = ThemePair::parse(theme) {
+            let contents =
+                Self::update_setting(contents, PREFERRED_LIGHT, Value::String(pair.light))?;
+            let contents =
+                Self::update_setting(&contents, PREFERRED_DARK, Value::String(pair.dark))?;
+            return Self::update_setting(&contents, AUTO_DETECT, Value::Bool(true));
+        }
+
+        let settings = match jsonc::parse(contents) {
+            Ok(Value::Object(settings)) => settings,
+            _ => Map::new(),
+        };
+        let key = if Self::auto_detects(&settings) {
+            Self::preferred_key(appearance)
+        } else {
+            COLOR_THEME
+        };
+        Self::update_setting(contents, key, Value::String(theme.to_string()))
+    }
+
+    /// Returns the theme VS Code is currently showing
+    pub fn effective_theme(
+        settings: &Map<String, Value>,
+        appearance: Option<Appearance>,
+    ) -> String {
+        let (key, default) = if !Self::auto_detects(settings) {
+            (COLOR_THEME, "Default Dark+")
+        } else if Self::preferred_key(appearance) == PREFERRED_LIGHT {
+            (PREFERRED_LIGHT, "Default Light Modern")
+        } else {
+            (PREFERRED_DARK, "Default Dark Modern")
+        };
+
+        match settings.get(key) {
+            Some(Value::String(theme)) => theme.clone(),
+            _ => default.to_string(),
+        }
+    }
+
+    async fn appearance_for(&self, settings: &Map<String, Value>) -> Option<Appearance> {
+        if Self::auto_detects(settings) {
+            detect_appearance(self.runner.as_ref()).await
+        } else {
+            None
+        }
+    }
 }
 
 #[async_trait::async_trait]
 impl ThemeAdapter for VsCodeAdapter {
     async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
-        let mut settings = self.read_settings()?;
-
-        settings.insert(
-            "workbench.colorTheme".to_string(),
-            Value::String(theme.to_string()),
-        );
+        let contents = self.read_contents()?;
+        let settings = self.read_settings()?;
+        let appearance = self.appearance_for(&settings).await;
 
-        self.write_settings(&settings)?;
+        // update_theme doesn't know which variant or profile it is editing for
+        let updated = Self::update_theme(&contents, theme, appearance).map_err(|e| ThemeError {
+            app_name: self.config_key.clone(),
+            ..e
+        })?;
+        self.write_contents(&updated)?;
         Ok(())
     }
 
     async fn get_theme(&self) -> Result<String, ThemeError> {
         let settings = self.read_settings()?;
+        let appearance = self.appearance_for(&settings).await;
 
-        match settings.get("workbench.colorTheme") {
-            Some(Value::String(theme)) => Ok(theme.clone()),
-            _ => Ok("Default Dark+".to_string()), // VS Code default
-        }
+        Ok(Self::effective_theme(&settings, appearance))
     }
 
     async fn is_available(&self) -> bool {
-        // Check if VS Code is installed by looking for the executable
-        let vscode_paths = [
-            "/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code",
-            "/usr/local/bin/code",
-            "/usr/bin/code",
-        ];
+        if self.path_override || self.settings_path.is_file() {
+            return true;
+        }
 
-        vscode_paths.iter().any(|path| PathBuf::from(path).exists())
+        if self
+            .variant
+            .install_paths()
+            .iter()
+            .any(|path| path.exists())
+        {
+            return true;
+        }
+
+        // Catches installs outside the usual prefixes, e.g. NixOS and home-manager profiles
+        let (program, args) = self.variant.cli_probe();
+        self.runner
+            .run(program, args)
+            .await
+            .is_ok_and(|output| output.success)
     }
 
     fn app_name(&self) -> &str {
-        "VS Code"
+        &self.app_name
     }
 
     fn config_key(&self) -> &str {
-        "vscode"
+        &self.config_key
     }
 
     fn default_themes(&self) -> HashMap<String, String> {
@@ -123,4 +440,21 @@ impl ThemeAdapter for VsCodeAdapter {
         themes.insert("light".to_string(), "GitHub Light".to_string());
         themes
     }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.settings_path.clone()]
+    }
+}
+
+/// Turns a profile name into a config key segment: `Work Stuff` becomes `work-stuff`
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
 }
@@ -0,0 +1,182 @@
+//! Adapter for WezTerm theme synchronization via a generated Lua module.
+//!
+//! WezTerm configs are arbitrary Lua, so themesync never edits them. Instead it owns
+//! `themesync.lua`, which returns the `color_scheme` name, and the user requires it once:
+//! `config.color_scheme = require("themesync")`. WezTerm watches required files and
+//! reloads on every switch.
+//!
+//! # Configuration Locations
+//! - macOS: `~/.config/wezterm/themesync.lua`
+//! - Linux: `~/.config/wezterm/themesync.lua`
+//!
+//! # Default Themes
+//! - dark: Tokyo Night
+//! - light: Catppuccin Latte
+
+use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::lua;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+const MODULE_NAME: &str = "themesync";
+
+pub struct WezTermAdapter {
+    module_path: PathBuf,
+    config_paths: Vec<PathBuf>,
+}
+
+impl Default for WezTermAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WezTermAdapter {
+    pub fn new() -> Self {
+        let config_dir = dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config")
+            .join("wezterm");
+
+        Self::with_path(config_dir.join(format!("{}.lua", MODULE_NAME)))
+    }
+
+    /// Creates an adapter that writes the generated `themesync.lua` to `module_path`
+    ///
+    /// The user's `wezterm.lua` is looked up next to it, then at `~/.wezterm.lua`, in
+    /// the order WezTerm itself searches.
+    pub fn with_path(module_path: PathBuf) -> Self {
+        let mut config_paths = Vec::new();
+        if let Some(dir) = module_path.parent() {
+            config_paths.push(dir.join("wezterm.lua"));
+        }
+        if let Some(home) = dirs::home_dir() {
+            config_paths.push(home.join(".wezterm.lua"));
+        }
+
+        Self {
+            module_path,
+            config_paths,
+        }
+    }
+
+    /// Returns the contents of the generated module for `theme`
+    pub fn render_module(theme: &str) -> String {
+        format!(
+            "-- Generated by themesync; changes are overwritten on the next theme switch.\n\
+             -- Use it from wezterm.lua with: config.color_scheme = require(\"{}\")\n\
+             return {}\n",
+            MODULE_NAME,
+            lua::quote(theme)
+        )
+    }
+
+    /// Returns the scheme name from the module's `return "..."` line
+    pub fn extract_theme_from_module(module: &str) -> Option<String> {
+        let value = module
+            .lines()
+            .map(str::trim)
+            .rfind(|line| line.starts_with("return "))?
+            .strip_prefix("return ")?;
+        lua::unquote(value)
+    }
+
+    /// Whether `config` requires the generated module
+    pub fn requires_module(config: &str) -> bool {
+        lua::requires_module(config, MODULE_NAME)
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for WezTermAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        write_atomic(&self.module_path, Self::render_module(theme)).map_err(|e| ThemeError {
+            message: format!("Failed to write {}: {}", self.module_path.display(), e),
+            app_name: "wezterm".to_string(),
+        })
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        if !self.module_path.exists() {
+            return Ok("default".to_string()); // WezTerm built-in colors
+        }
+
+        let module = std::fs::read_to_string(&self.module_path).map_err(|e| ThemeError {
+            message: format!("Failed to read {}: {}", self.module_path.display(), e),
+            app_name: "wezterm".to_string(),
+        })?;
+
+        Self::extract_theme_from_module(&module).ok_or_else(|| ThemeError {
+            message: format!(
+                "{} does not return a color scheme name",
+                self.module_path.display()
+            ),
+            app_name: "wezterm".to_string(),
+        })
+    }
+
+    async fn is_available(&self) -> bool {
+        let wezterm_paths = [
+            "/Applications/WezTerm.app/Contents/MacOS/wezterm",
+            "/usr/local/bin/wezterm",
+            "/usr/bin/wezterm",
+            "/opt/homebrew/bin/wezterm",
+        ];
+
+        if wezterm_paths
+            .iter()
+            .any(|path| PathBuf::from(path).exists())
+        {
+            return true;
+        }
+
+        Command::new("wezterm")
+            .arg("--version")
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    fn app_name(&self) -> &str {
+        "WezTerm"
+    }
+
+    fn config_key(&self) -> &str {
+        "wezterm"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "Tokyo Night".to_string());
+        themes.insert("light".to_string(), "Catppuccin Latte".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.module_path.clone()]
+    }
+
+    async fn doctor(&self) -> Vec<String> {
+        let Some(config_path) = self.config_paths.iter().find(|path| path.is_file()) else {
+            return vec![format!(
+                "No wezterm.lua found; create one that sets config.color_scheme = require(\"{}\")",
+                MODULE_NAME
+            )];
+        };
+
+        match std::fs::read_to_string(config_path) {
+            Ok(config) if Self::requires_module(&config) => Vec::new(),
+            Ok(_) => vec![format!(
+                "{} does not load the generated theme; add config.color_scheme = require(\"{}\")",
+                config_path.display(),
+                MODULE_NAME
+            )],
+            Err(e) => vec![format!("Failed to read {}: {}", config_path.display(), e)],
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Adapter for Windows Terminal theme synchronization from WSL via `settings.json`.
+//!
+//! Windows Terminal keeps its settings on the Windows side, which WSL mounts under
+//! `/mnt/c`. A theme value is the color scheme for all profiles, optionally followed by
+//! `|` and the window theme, e.g. `One Half Dark|dark`. The adapter reports itself as
+//! unavailable outside of WSL.
+//!
+//! # Configuration Locations
+//! - WSL (Store): `/mnt/c/Users/<user>/AppData/Local/Packages/Microsoft.WindowsTerminal_<id>/LocalState/settings.json`
+//! - WSL (Preview): `/mnt/c/Users/<user>/AppData/Local/Packages/Microsoft.WindowsTerminalPreview_<id>/LocalState/settings.json`
+//! - WSL (unpackaged): `/mnt/c/Users/<user>/AppData/Local/Microsoft/Windows Terminal/settings.json`
+//!
+//! # Default Themes
+//! - dark: One Half Dark|dark
+//! - light: One Half Light|light
+
+use crate::adapter::{ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use crate::jsonc;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Package folder prefixes of the Store builds: stable and Preview
+const PACKAGE_PREFIXES: &[&str] = &[
+    "Microsoft.WindowsTerminal_",
+    "Microsoft.WindowsTerminalPreview_",
+];
+
+/// Settings folder of unpackaged installs (zip, winget portable, scoop)
+const UNPACKAGED_DIR: &str = "AppData/Local/Microsoft/Windows Terminal";
+
+/// Whether a `/proc/version` string belongs to a WSL kernel
+pub fn is_wsl_kernel(proc_version: &str) -> bool {
+    proc_version.to_lowercase().contains("microsoft")
+}
+
+/// Whether themesync is running under WSL
+fn is_wsl() -> bool {
+    std::env::var_os("WSL_DISTRO_NAME").is_some()
+        || Path::new("/proc/sys/fs/binfmt_misc/WSLInterop").exists()
+        || std::fs::read_to_string("/proc/version").is_ok_and(|version| is_wsl_kernel(&version))
+}
+
+pub struct WindowsTerminalAdapter {
+    /// Where the Windows system drive is mounted
+    root: PathBuf,
+    /// Explicit settings file, bypassing discovery under `root`
+    settings_path: Option<PathBuf>,
+}
+
+impl Default for WindowsTerminalAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WindowsTerminalAdapter {
+    pub fn new() -> Self {
+        Self::with_root(PathBuf::from("/mnt/c"))
+    }
+
+    /// Creates an adapter that looks for Windows user profiles under `root` instead of `/mnt/c`
+    pub fn with_root(root: PathBuf) -> Self {
+        Self {
+            root,
+            settings_path: None,
+        }
+    }
+
+    /// Creates an adapter that edits the `settings.json` at `settings_path` instead of discovering it
+    pub fn with_path(settings_path: PathBuf) -> Self {
+        Self {
+            settings_path: Some(settings_path),
+            ..Self::new()
+        }
+    }
+
+    /// Returns the `settings.json` of every Windows Terminal install of every Windows user
+    pub fn discover_settings(root: &Path) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        for user in std::fs::read_dir(root.join("Users"))
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let packages = user.path().join("AppData/Local/Packages");
+            for package in std::fs::read_dir(packages).into_iter().flatten().flatten() {
+                let name = package.file_name().to_string_lossy().into_owned();
+                if !PACKAGE_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+                {
+                    continue;
+                }
+                let settings = package.path().join("LocalState/settings.json");
+                if settings.is_file() {
+                    paths.push(settings);
+                }
+            }
+
+            let unpackaged = user.path().join(UNPACKAGED_DIR).join("settings.json");
+            if unpackaged.is_file() {
+                paths.push(unpackaged);
+            }
+        }
+        paths.sort();
+        paths
+    }
+
+    fn settings_paths(&self) -> Vec<PathBuf> {
+        match &self.settings_path {
+            Some(path) => vec![path.clone()],
+            None => Self::discover_settings(&self.root),
+        }
+    }
+
+    fn error(message: String) -> ThemeError {
+        ThemeError {
+            message,
+            app_name: "windows-terminal".to_string(),
+        }
+    }
+
+    fn parse_settings(contents: &str) -> Result<Map<String, Value>, ThemeError> {
+        match jsonc::parse(contents) {
+            Ok(Value::Object(map)) => Ok(map),
+            Ok(_) => Err(Self::error(
+                "settings.json is not a JSON object".to_string(),
+            )),
+            Err(e) => Err(Self::error(format!("Failed to parse settings.json: {}", e))),
+        }
+    }
+
+    /// Splits a theme value into the color scheme and optional window theme
+    pub fn parse_theme(theme: &str) -> (&str, Option<&str>) {
+        match theme.split_once('|') {
+            Some((scheme, window_theme)) => (scheme.trim(), Some(window_theme.trim())),
+            None => (theme.trim(), None),
+        }
+    }
+
+    /// Writes `theme` into the contents of `settings.json`, keeping comments intact
+    pub fn update_settings(contents: &str, theme: &str) -> Result<String, ThemeError> {
+        let settings = Self::parse_settings(contents)?;
+        if matches!(settings.get("profiles"), Some(Value::Array(_))) {
+            // Profile defaults only exist in the { "defaults", "list" } form
+            return Err(Self::error(
+                "\"profiles\" is a list; move it into \"profiles\": { \"list\": [...] }"
+                    .to_string(),
+            ));
+        }
+
+        let (scheme, window_theme) = Self::parse_theme(theme);
+        let set = |contents: &str, path: &[&str], value: Value| {
+            jsonc::set_value(contents, path, &value)
+                .map_err(|e| Self::error(format!("Failed to update settings.json: {}", e)))
+        };
+
+        let mut contents = set(
+            contents,
+            &["profiles", "defaults", "colorScheme"],
+            json!(scheme),
+        )?;
+        if let Some(window_theme) = window_theme {
+            contents = set(&contents, &["theme"], json!(window_theme))?;
+        }
+        Ok(contents)
+    }
+
+    /// Returns the theme value described by `settings`
+    pub fn extract_theme(settings: &Map<String, Value>) -> String {
+        let scheme = settings
+            .get("profiles")
+            .and_then(|profiles| profiles.get("defaults"))
+            .and_then(|defaults| defaults.get("colorScheme"))
+            .and_then(Value::as_str)
+            .unwrap_or("Campbell"); // Windows Terminal default
+
+        match settings.get("theme").and_then(Value::as_str) {
+            Some(window_theme) => format!("{}|{}", scheme, window_theme),
+            None => scheme.to_string(),
+        }
+    }
+
+    fn read_contents(path: &Path) -> Result<String, ThemeError> {
+        std::fs::read_to_string(path)
+            .map_err(|e| Self::error(format!("Failed to read {}: {}", path.display(), e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for WindowsTerminalAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let paths = self.settings_paths();
+        if paths.is_empty() {
+            return Err(Self::error(format!(
+                "No Windows Terminal settings.json found under {}",
+                self.root.join("Users").display()
+            )));
+        }
+
+        for path in paths {
+            let updated = Self::update_settings(&Self::read_contents(&path)?, theme)?;
+            write_atomic(&path, updated)
+                .map_err(|e| Self::error(format!("Failed to write {}: {}", path.display(), e)))?;
+        }
+        Ok(())
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let Some(path) = self.settings_paths().into_iter().next() else {
+            return Ok("Campbell".to_string());
+        };
+
+        let settings = Self::parse_settings(&Self::read_contents(&path)?)?;
+        Ok(Self::extract_theme(&settings))
+    }
+
+    async fn is_available(&self) -> bool {
+        is_wsl() && !self.settings_paths().is_empty()
+    }
+
+    fn app_name(&self) -> &str {
+        "Windows Terminal"
+    }
+
+    fn config_key(&self) -> &str {
+        "windows-terminal"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "One Half Dark|dark".to_string());
+        themes.insert("light".to_string(), "One Half Light|light".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        self.settings_paths()
+    }
+}
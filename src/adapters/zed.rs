@@ -0,0 +1,232 @@
+//! Adapter for Zed theme synchronization via `settings.json`.
+//!
+//! Zed's `theme` setting is either a theme name or an object with a `mode`
+//! (`system`, `light` or `dark`) and one theme per variant. Plain theme names go into
+//! the variant currently in effect; `light:X,dark:Y` pairs set both.
+//!
+//! # Configuration Locations
+//! - macOS: `~/.config/zed/settings.json`
+//! - Linux: `~/.config/zed/settings.json`
+//!
+//! # Default Themes
+//! - dark: One Dark
+//! - light: One Light
+
+use crate::adapter::{ThemeAdapter, ThemeError, ThemePair};
+use crate::appearance::{detect_appearance, Appearance};
+use crate::atomic_write::write_atomic;
+use crate::jsonc;
+use crate::runner::{CommandRunner, SystemRunner};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub struct ZedAdapter {
+    settings_path: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for ZedAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZedAdapter {
+    pub fn new() -> Self {
+        // Zed reads ~/.config on macOS too, not ~/Library
+        let settings_path = dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config")
+            .join("zed")
+            .join("settings.json");
+
+        Self::with_path(settings_path)
+    }
+
+    /// Creates an adapter that edits the `theme` setting in the Zed `settings.json` at `settings_path`
+    pub fn with_path(settings_path: PathBuf) -> Self {
+        Self {
+            settings_path,
+            runner: Arc::new(SystemRunner::new()),
+        }
+    }
+
+    /// Asks the OS for its appearance through `runner` when `theme.mode` is `system`
+    pub fn with_runner(self, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { runner, ..self }
+    }
+
+    fn read_contents(&self) -> Result<String, ThemeError> {
+        if !self.settings_path.exists() {
+            return Ok(String::new());
+        }
+
+        std::fs::read_to_string(&self.settings_path).map_err(|e| ThemeError {
+            message: format!("Failed to read settings.json: {}", e),
+            app_name: "zed".to_string(),
+        })
+    }
+
+    fn parse_settings(contents: &str) -> Result<Map<String, Value>, ThemeError> {
+        let value = jsonc::parse(contents).map_err(|e| ThemeError {
+            message: format!("Failed to parse settings.json: {}", e),
+            app_name: "zed".to_string(),
+        })?;
+
+        match value {
+            Value::Object(map) => Ok(map),
+            _ => Err(ThemeError {
+                message: "settings.json is not a JSON object".to_string(),
+                app_name: "zed".to_string(),
+            }),
+        }
+    }
+
+    /// Returns the variant (`"light"` or `"dark"`) of a theme object that is in effect
+    ///
+    /// `system` mode, which is also Zed's default, follows `appearance`; Zed itself
+    /// falls back to dark when the system appearance is unknown.
+    pub fn active_variant(
+        theme: &Map<String, Value>,
+        appearance: Option<Appearance>,
+    ) -> &'static str {
+        match theme.get("mode").and_then(Value::as_str) {
+            Some("light") => "light",
+            Some("dark") => "dark",
+            _ => appearance.unwrap_or(Appearance::Dark).as_str(),
+        }
+    }
+
+    /// Whether resolving the active variant of `settings` needs the system appearance
+    fn follows_system(settings: &Map<String, Value>) -> bool {
+        match settings.get("theme") {
+            Some(Value::Object(theme)) => !matches!(
+                theme.get("mode").and_then(Value::as_str),
+                Some("light" | "dark")
+            ),
+            _ => false,
+        }
+    }
+
+    /// Writes `theme` into the `theme` setting of `contents`, keeping comments intact
+    ///
+    /// A plain name replaces a string setting, or the active variant of an object
+    /// setting. A pair sets both variants, converting a string setting to a
+    /// `system`-mode object.
+    pub fn update_theme(
+        contents: &str,
+        theme: &str,
+        appearance: Option<Appearance>,
+    ) -> Result<String, ThemeError> {
+        let settings = Self::parse_settings(contents)?;
+        let set = |contents: &str, path: &[&str], value: Value| {
+            jsonc::set_value(contents, path, &value).map_err(|e| ThemeError {
+                message: format!("Failed to update settings.json: {}", e),
+                app_name: "zed".to_string(),
+            })
+        };
+
+        match (ThemePair::parse(theme), settings.get("theme")) {
+            (Some(pair), current) => {
+                let contents = match current {
+                    Some(Value::Object(_)) => contents.to_string(),
+                    // Written member by member so the object reads mode, light, dark
+                    _ => set(contents, &["theme"], json!({ "mode": "system" }))?,
+                };
+                let contents = set(&contents, &["theme", "light"], json!(pair.light))?;
+                set(&contents, &["theme", "dark"], json!(pair.dark))
+            }
+            (None, Some(Value::Object(current))) => {
+                let variant = Self::active_variant(current, appearance);
+                set(contents, &["theme", variant], json!(theme))
+            }
+            (None, _) => set(contents, &["theme"], json!(theme)),
+        }
+    }
+
+    /// Returns the theme Zed is currently showing, if `settings` configures one
+    pub fn effective_theme(
+        settings: &Map<String, Value>,
+        appearance: Option<Appearance>,
+    ) -> Option<String> {
+        match settings.get("theme")? {
+            Value::String(theme) => Some(theme.clone()),
+            Value::Object(theme) => theme
+                .get(Self::active_variant(theme, appearance))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        }
+    }
+
+    async fn appearance_for(&self, settings: &Map<String, Value>) -> Option<Appearance> {
+        if Self::follows_system(settings) {
+            detect_appearance(self.runner.as_ref()).await
+        } else {
+            None
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ThemeAdapter for ZedAdapter {
+    async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+        let contents = self.read_contents()?;
+        let settings = Self::parse_settings(&contents)?;
+        let appearance = self.appearance_for(&settings).await;
+
+        let updated = Self::update_theme(&contents, theme, appearance)?;
+        write_atomic(&self.settings_path, updated).map_err(|e| ThemeError {
+            message: format!("Failed to write settings.json: {}", e),
+            app_name: "zed".to_string(),
+        })
+    }
+
+    async fn get_theme(&self) -> Result<String, ThemeError> {
+        let settings = Self::parse_settings(&self.read_contents()?)?;
+        let appearance = self.appearance_for(&settings).await;
+
+        match Self::effective_theme(&settings, appearance) {
+            Some(theme) => Ok(theme),
+            None => Ok("One Dark".to_string()), // Zed default
+        }
+    }
+
+    async fn is_available(&self) -> bool {
+        let zed_paths = [
+            "/Applications/Zed.app/Contents/MacOS/zed",
+            "/usr/local/bin/zed",
+            "/usr/bin/zed",
+            "/usr/bin/zeditor",
+        ];
+
+        if zed_paths.iter().any(|path| PathBuf::from(path).exists()) {
+            return true;
+        }
+
+        // The install script puts the CLI in ~/.local/bin
+        dirs::home_dir().is_some_and(|home| home.join(".local/bin/zed").exists())
+    }
+
+    fn app_name(&self) -> &str {
+        "Zed"
+    }
+
+    fn config_key(&self) -> &str {
+        "zed"
+    }
+
+    fn default_themes(&self) -> HashMap<String, String> {
+        let mut themes = HashMap::new();
+        themes.insert("dark".to_string(), "One Dark".to_string());
+        themes.insert("light".to_string(), "One Light".to_string());
+        themes
+    }
+
+    fn config_files(&self) -> Vec<PathBuf> {
+        vec![self.settings_path.clone()]
+    }
+}
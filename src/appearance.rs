@@ -0,0 +1,57 @@
+//! Detection of the OS-wide light/dark appearance.
+//!
+//! Used by adapters whose apps follow the system appearance, to decide which variant
+//! of a light/dark pair is currently in effect.
+
+use crate::runner::CommandRunner;
+
+/// The OS light/dark preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    /// Returns `"light"` or `"dark"`, the key used for each variant in theme pairs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Appearance::Light => "light",
+            Appearance::Dark => "dark",
+        }
+    }
+}
+
+/// Asks the OS for its current appearance; returns `None` if it can't be determined
+///
+/// macOS reports dark mode via `AppleInterfaceStyle`, which is unset in light mode.
+/// Elsewhere the freedesktop `color-scheme` setting is read through `gsettings`.
+pub async fn detect_appearance(runner: &dyn CommandRunner) -> Option<Appearance> {
+    if cfg!(target_os = "macos") {
+        let output = runner
+            .run("defaults", &["read", "-g", "AppleInterfaceStyle"])
+            .await
+            .ok()?;
+        return Some(if output.success && output.stdout.trim() == "Dark" {
+            Appearance::Dark
+        } else {
+            Appearance::Light
+        });
+    }
+
+    let output = runner
+        .run(
+            "gsettings",
+            &["get", "org.gnome.desktop.interface", "color-scheme"],
+        )
+        .await
+        .ok()?;
+    if !output.success {
+        return None;
+    }
+    match output.stdout.trim().trim_matches('\'') {
+        "prefer-dark" => Some(Appearance::Dark),
+        "prefer-light" | "default" => Some(Appearance::Light),
+        _ => None,
+    }
+}
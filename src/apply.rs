@@ -0,0 +1,386 @@
+use crate::adapter::{ApplyMethod, ThemeAdapter, ThemeError};
+use crate::atomic_write::write_atomic;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Upper bound on adapters applied at the same time in parallel mode
+const MAX_CONCURRENT_ADAPTERS: usize = 4;
+
+#[derive(Debug)]
+pub enum ApplyOutcome {
+    /// The adapter switched to the contained app theme
+    Applied(String),
+    Failed(ThemeError),
+    Unavailable,
+    /// The theme has no mapping for this adapter, so it was left alone
+    NotMapped,
+    /// Detection or switching took longer than the configured timeout
+    TimedOut,
+}
+
+impl ApplyOutcome {
+    /// Whether this outcome should abort an atomic switch
+    pub fn is_failure(&self) -> bool {
+        matches!(self, ApplyOutcome::Failed(_) | ApplyOutcome::TimedOut)
+    }
+}
+
+#[derive(Debug)]
+pub struct AdapterReport {
+    pub app_name: String,
+    pub outcome: ApplyOutcome,
+    /// Whether running instances of the app must be restarted to show a new theme
+    pub needs_restart: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApplyOptions {
+    pub parallel: bool,
+    /// Time budget for each adapter, covering both `is_available` and `set_theme`;
+    /// atomic switches give taking the snapshot a budget of its own
+    pub timeout: Duration,
+    /// Apply method per config key; adapters without an entry use `ApplyMethod::Auto`
+    pub methods: HashMap<String, ApplyMethod>,
+}
+
+impl ApplyOptions {
+    fn method_for(&self, adapter: &dyn ThemeAdapter) -> ApplyMethod {
+        self.methods
+            .get(adapter.config_key())
+            .copied()
+            .unwrap_or(ApplyMethod::Auto)
+    }
+}
+
+/// Applies `mapping` (config key to app theme) to every adapter
+///
+/// Reports are returned in the same order as `adapters`, regardless of whether the
+/// adapters ran concurrently.
+pub async fn apply_theme(
+    adapters: &[Arc<dyn ThemeAdapter>],
+    mapping: &HashMap<String, String>,
+    options: &ApplyOptions,
+) -> Vec<AdapterReport> {
+    apply_with_availability(adapters, mapping, options, &vec![None; adapters.len()]).await
+}
+
+/// Like `apply_theme`, but skips `is_available` for adapters whose availability is
+/// already known
+async fn apply_with_availability(
+    adapters: &[Arc<dyn ThemeAdapter>],
+    mapping: &HashMap<String, String>,
+    options: &ApplyOptions,
+    available: &[Option<bool>],
+) -> Vec<AdapterReport> {
+    let outcomes = run_each(adapters, options, |index, adapter| {
+        let theme = mapping.get(adapter.config_key()).cloned();
+        let method = options.method_for(adapter.as_ref());
+        apply_to_adapter(adapter, theme, method, available[index])
+    })
+    .await;
+
+    adapters
+        .iter()
+        .zip(outcomes)
+        .map(|(adapter, outcome)| AdapterReport {
+            app_name: adapter.app_name().to_string(),
+            outcome: outcome.unwrap_or_else(|outcome| outcome),
+            needs_restart: adapter.needs_restart(),
+        })
+        .collect()
+}
+
+/// Runs `job` for every adapter, each within `options.timeout`
+///
+/// Jobs run concurrently (at most `MAX_CONCURRENT_ADAPTERS` at a time) when
+/// `options.parallel` is set. Results are in the same order as `adapters`; a job that
+/// times out or panics yields the outcome to report for its adapter instead.
+async fn run_each<T, F, Fut>(
+    adapters: &[Arc<dyn ThemeAdapter>],
+    options: &ApplyOptions,
+    job: F,
+) -> Vec<Result<T, ApplyOutcome>>
+where
+    F: Fn(usize, Arc<dyn ThemeAdapter>) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let mut results = Vec::with_capacity(adapters.len());
+
+    if options.parallel {
+        let limit = Arc::new(Semaphore::new(MAX_CONCURRENT_ADAPTERS));
+        let mut tasks = JoinSet::new();
+
+        for (index, adapter) in adapters.iter().enumerate() {
+            let app_name = adapter.config_key().to_string();
+            let job = job(index, Arc::clone(adapter));
+            let limit = Arc::clone(&limit);
+            let timeout = options.timeout;
+
+            tasks.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                (index, with_timeout(job, app_name, timeout).await)
+            });
+        }
+
+        results.resize_with(adapters.len(), || None);
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((index, result)) = joined {
+                results[index] = Some(result);
+            }
+        }
+    } else {
+        for (index, adapter) in adapters.iter().enumerate() {
+            let app_name = adapter.config_key().to_string();
+            let job = job(index, Arc::clone(adapter));
+            results.push(Some(with_timeout(job, app_name, options.timeout).await));
+        }
+    }
+
+    adapters
+        .iter()
+        .zip(results)
+        .map(|(adapter, result)| {
+            result.unwrap_or_else(|| {
+                Err(ApplyOutcome::Failed(ThemeError {
+                    message: "Adapter task panicked".to_string(),
+                    app_name: adapter.config_key().to_string(),
+                }))
+            })
+        })
+        .collect()
+}
+
+async fn with_timeout<T: Send + 'static>(
+    job: impl Future<Output = T> + Send + 'static,
+    app_name: String,
+    timeout: Duration,
+) -> Result<T, ApplyOutcome> {
+    // Run on its own task so the timeout still fires if the adapter blocks its thread
+    let mut task = tokio::spawn(job);
+
+    match tokio::time::timeout(timeout, &mut task).await {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => Err(ApplyOutcome::Failed(ThemeError {
+            message: format!("Adapter task failed: {}", e),
+            app_name,
+        })),
+        Err(_) => {
+            task.abort();
+            Err(ApplyOutcome::TimedOut)
+        }
+    }
+}
+
+async fn apply_to_adapter(
+    adapter: Arc<dyn ThemeAdapter>,
+    theme: Option<String>,
+    method: ApplyMethod,
+    available: Option<bool>,
+) -> ApplyOutcome {
+    let available = match available {
+        Some(available) => available,
+        None => adapter.is_available().await,
+    };
+    if !available {
+        return ApplyOutcome::Unavailable;
+    }
+
+    match theme {
+        Some(theme) => match adapter.set_theme_with(&theme, method).await {
+            Ok(()) => ApplyOutcome::Applied(theme),
+            Err(e) => ApplyOutcome::Failed(e),
+        },
+        None => ApplyOutcome::NotMapped,
+    }
+}
+
+/// Result of restoring one adapter after an atomic switch failed
+#[derive(Debug)]
+pub struct RollbackReport {
+    pub app_name: String,
+    pub result: Result<(), ThemeError>,
+}
+
+#[derive(Debug)]
+pub enum AtomicOutcome {
+    /// Every mapped adapter switched (or was unavailable); nothing was undone
+    Committed(Vec<AdapterReport>),
+    /// At least one adapter failed, so every attempted adapter was restored
+    RolledBack {
+        reports: Vec<AdapterReport>,
+        rollbacks: Vec<RollbackReport>,
+    },
+}
+
+/// State of an adapter captured before a switch so it can be put back
+///
+/// Config files are copied byte for byte, and state an adapter keeps elsewhere (a
+/// settings database, a desktop-wide setting) comes from `capture_state`; adapters
+/// that change both get both restored. Adapters with neither fall back to re-applying
+/// the theme from `get_theme`.
+/// Restoring files does not reach running instances, so for adapters that support
+/// `ApplyMethod::Ipc` the previous theme is also pushed live again. That push is best
+/// effort: if the old theme cannot be pushed (say it was not a themesync theme),
+/// running instances keep the new theme until they reload their config.
+#[derive(Debug, Clone)]
+pub struct AdapterSnapshot {
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+    /// Adapter-specific state from `capture_state`
+    state: Option<String>,
+    theme: Option<String>,
+    /// Theme to push to running instances after the files are restored
+    live_theme: Option<String>,
+}
+
+impl AdapterSnapshot {
+    pub async fn capture(adapter: &dyn ThemeAdapter) -> Result<Self, ThemeError> {
+        let mut files = Vec::new();
+        for path in adapter.config_files() {
+            let contents = match std::fs::read(&path) {
+                Ok(contents) => Some(contents),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(ThemeError {
+                        message: format!("Failed to snapshot {}: {}", path.display(), e),
+                        app_name: adapter.config_key().to_string(),
+                    })
+                }
+            };
+            files.push((path, contents));
+        }
+
+        let state = adapter.capture_state().await?;
+
+        let (theme, live_theme) = if files.is_empty() && state.is_none() {
+            (Some(adapter.get_theme().await?), None)
+        } else if adapter.supported_methods().contains(&ApplyMethod::Ipc) {
+            (None, adapter.get_theme().await.ok())
+        } else {
+            (None, None)
+        };
+
+        Ok(Self {
+            files,
+            state,
+            theme,
+            live_theme,
+        })
+    }
+
+    pub async fn restore(&self, adapter: &dyn ThemeAdapter) -> Result<(), ThemeError> {
+        let io_error = |path: &PathBuf, e: std::io::Error| ThemeError {
+            message: format!("Failed to restore {}: {}", path.display(), e),
+            app_name: adapter.config_key().to_string(),
+        };
+
+        // Pushed before the files are restored, since some adapters rewrite their
+        // config on the way; the byte-for-byte restore below then wins
+        if let Some(theme) = &self.live_theme {
+            let _ = adapter.set_theme_with(theme, ApplyMethod::Ipc).await;
+        }
+        if let Some(state) = &self.state {
+            adapter.restore_state(state).await?;
+        }
+
+        for (path, contents) in &self.files {
+            match contents {
+                Some(contents) => write_atomic(path, contents).map_err(|e| io_error(path, e))?,
+                // The file did not exist before the switch, so remove what the adapter created
+                None if path.exists() => {
+                    std::fs::remove_file(path).map_err(|e| io_error(path, e))?
+                }
+                None => {}
+            }
+        }
+
+        if let Some(theme) = &self.theme {
+            adapter.set_theme(theme).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies `mapping` all-or-nothing: if any adapter fails, every adapter that was
+/// attempted is restored to the state it had before the switch
+///
+/// Availability checks and snapshots run under the same concurrency limit and
+/// timeout as the switch itself. Unavailable adapters are skipped. An adapter whose
+/// state cannot be captured in time is not switched, since it could not be put back,
+/// and is reported as failed or timed out.
+pub async fn apply_theme_atomic(
+    adapters: &[Arc<dyn ThemeAdapter>],
+    mapping: &HashMap<String, String>,
+    options: &ApplyOptions,
+) -> AtomicOutcome {
+    let captures = run_each(adapters, options, |_, adapter| {
+        let mapped = mapping.contains_key(adapter.config_key());
+        async move {
+            if !adapter.is_available().await {
+                return (false, None);
+            }
+            let snapshot = if mapped {
+                Some(AdapterSnapshot::capture(adapter.as_ref()).await)
+            } else {
+                None
+            };
+            (true, snapshot)
+        }
+    })
+    .await;
+
+    let mut snapshots = Vec::with_capacity(adapters.len());
+    let mut capture_failures = Vec::with_capacity(adapters.len());
+    let mut available = Vec::with_capacity(adapters.len());
+    let mut switchable = mapping.clone();
+    for (adapter, capture) in adapters.iter().zip(captures) {
+        let (snapshot, failure) = match capture {
+            Ok((is_available, snapshot)) => {
+                available.push(Some(is_available));
+                match snapshot {
+                    Some(Ok(snapshot)) => (Some(snapshot), None),
+                    Some(Err(e)) => (None, Some(ApplyOutcome::Failed(e))),
+                    None => (None, None),
+                }
+            }
+            Err(outcome) => {
+                available.push(Some(false));
+                (None, Some(outcome))
+            }
+        };
+        if failure.is_some() {
+            switchable.remove(adapter.config_key());
+        }
+        snapshots.push(snapshot);
+        capture_failures.push(failure);
+    }
+
+    let mut reports = apply_with_availability(adapters, &switchable, options, &available).await;
+    for (report, failure) in reports.iter_mut().zip(capture_failures) {
+        if let Some(outcome) = failure {
+            report.outcome = outcome;
+        }
+    }
+    if !reports.iter().any(|report| report.outcome.is_failure()) {
+        return AtomicOutcome::Committed(reports);
+    }
+
+    let mut rollbacks = Vec::new();
+    for ((adapter, snapshot), report) in adapters.iter().zip(&snapshots).zip(&reports) {
+        let attempted =
+            matches!(report.outcome, ApplyOutcome::Applied(_)) || report.outcome.is_failure();
+        if let (true, Some(snapshot)) = (attempted, snapshot) {
+            rollbacks.push(RollbackReport {
+                app_name: report.app_name.clone(),
+                result: snapshot.restore(adapter.as_ref()).await,
+            });
+        }
+    }
+
+    AtomicOutcome::RolledBack { reports, rollbacks }
+}
@@ -0,0 +1,226 @@
+use crate::atomic_write::write_atomic;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MANIFEST_FILE: &str = "manifest.yaml";
+
+/// A single app config file captured in a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub app: String,
+    pub original: PathBuf,
+    /// Location of the copy, relative to the backup directory
+    pub stored: PathBuf,
+}
+
+/// Metadata describing one backup, written as `manifest.yaml` next to the copied files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub id: String,
+    pub theme: Option<String>,
+    pub files: Vec<BackupEntry>,
+}
+
+/// Timestamped store of app config snapshots taken before themesync overwrites them
+///
+/// Each backup lives in its own `<root>/<id>/` directory, where the id is the UTC
+/// creation time (`YYYYMMDD-HHMMSS-mmm`) so lexical order matches creation order.
+pub struct BackupStore {
+    root: PathBuf,
+    retention: usize,
+}
+
+impl BackupStore {
+    /// Creates a store rooted at `root` that keeps at most `retention` backups (0 keeps all)
+    pub fn new(root: PathBuf, retention: usize) -> Self {
+        Self { root, retention }
+    }
+
+    pub fn default_root() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".local")
+            .join("state")
+            .join("themesync")
+            .join("backups")
+    }
+
+    /// Copies every existing file in `files` (pairs of app config key and path) into a new backup
+    ///
+    /// Returns `None` without touching the store when none of the files exist yet.
+    pub fn create(
+        &self,
+        files: &[(String, PathBuf)],
+        theme: Option<&str>,
+    ) -> Result<Option<BackupManifest>> {
+        let existing: Vec<_> = files.iter().filter(|(_, path)| path.is_file()).collect();
+        if existing.is_empty() {
+            return Ok(None);
+        }
+
+        let (id, dir) = self.allocate_dir()?;
+        let mut entries = Vec::new();
+
+        for (index, (app, original)) in existing.into_iter().enumerate() {
+            let file_name = original
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "config".to_string());
+            // Prefix with the index so two files with the same name never collide
+            let stored = PathBuf::from(app).join(format!("{}-{}", index, file_name));
+
+            std::fs::create_dir_all(dir.join(app))?;
+            std::fs::copy(original, dir.join(&stored))
+                .with_context(|| format!("Failed to back up {}", original.display()))?;
+
+            entries.push(BackupEntry {
+                app: app.clone(),
+                original: original.clone(),
+                stored,
+            });
+        }
+
+        let manifest = BackupManifest {
+            id,
+            theme: theme.map(str::to_string),
+            files: entries,
+        };
+        write_atomic(&dir.join(MANIFEST_FILE), serde_yaml::to_string(&manifest)?)?;
+
+        self.prune()?;
+        Ok(Some(manifest))
+    }
+
+    /// Lists all backups, newest first
+    ///
+    /// Manifests that cannot be read or parsed are skipped with a warning so one damaged
+    /// backup does not block creating, pruning or restoring the others.
+    pub fn list(&self) -> Result<Vec<BackupManifest>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut manifests = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let manifest_path = entry?.path().join(MANIFEST_FILE);
+            if !manifest_path.is_file() {
+                continue;
+            }
+
+            let manifest = std::fs::read_to_string(&manifest_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| Ok(serde_yaml::from_str::<BackupManifest>(&contents)?));
+            match manifest {
+                Ok(manifest) => manifests.push(manifest),
+                Err(e) => eprintln!(
+                    "Warning: skipping invalid backup manifest {}: {}",
+                    manifest_path.display(),
+                    e
+                ),
+            }
+        }
+
+        manifests.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(manifests)
+    }
+
+    /// Copies the files of backup `id` back to their original locations
+    ///
+    /// When `app` is given only that app's files are restored. Returns the restored paths.
+    pub fn restore(&self, id: &str, app: Option<&str>) -> Result<Vec<PathBuf>> {
+        let manifest = self
+            .list()?
+            .into_iter()
+            .find(|manifest| manifest.id == id)
+            .ok_or_else(|| anyhow!("Backup '{}' not found", id))?;
+
+        let entries: Vec<_> = manifest
+            .files
+            .iter()
+            .filter(|entry| app.is_none_or(|app| entry.app == app))
+            .collect();
+
+        if entries.is_empty() {
+            return Err(anyhow!(
+                "Backup '{}' contains no files for '{}'",
+                id,
+                app.unwrap_or_default()
+            ));
+        }
+
+        let dir = self.root.join(&manifest.id);
+        let mut restored = Vec::new();
+        for entry in entries {
+            let contents = std::fs::read(dir.join(&entry.stored))?;
+            write_atomic(&entry.original, contents)
+                .with_context(|| format!("Failed to restore {}", entry.original.display()))?;
+            restored.push(entry.original.clone());
+        }
+
+        Ok(restored)
+    }
+
+    fn allocate_dir(&self) -> Result<(String, PathBuf)> {
+        std::fs::create_dir_all(&self.root)?;
+
+        let base = timestamp_id(SystemTime::now());
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while self.root.join(&id).exists() {
+            id = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        let dir = self.root.join(&id);
+        std::fs::create_dir(&dir)?;
+        Ok((id, dir))
+    }
+
+    fn prune(&self) -> Result<()> {
+        if self.retention == 0 {
+            return Ok(());
+        }
+
+        for manifest in self.list()?.into_iter().skip(self.retention) {
+            let dir = self.root.join(&manifest.id);
+            std::fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to remove old backup {}", dir.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a time as `YYYYMMDD-HHMMSS-mmm` in UTC
+fn timestamp_id(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = elapsed.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        (secs_of_day % 3600) / 60,
+        secs_of_day % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+// Howard Hinnant's days-to-civil algorithm, avoiding a date/time dependency
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
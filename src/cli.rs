@@ -15,15 +15,21 @@ pub enum Commands {
     Set {
         #[arg(help = "Theme name to set")]
         theme: String,
+        #[arg(long, help = "Roll back every app if any app fails to switch")]
+        atomic: bool,
     },
     #[command(about = "Toggle between current and previously used theme")]
     Toggle,
     #[command(about = "Show current theme state across all apps")]
     Status,
+    #[command(about = "Check installed apps for setup problems")]
+    Doctor,
     #[command(subcommand)]
     Themes(ThemeCommands),
     #[command(subcommand)]
     Apps(AppCommands),
+    #[command(subcommand)]
+    Backup(BackupCommands),
 }
 
 #[derive(Subcommand)]
@@ -37,3 +43,19 @@ pub enum AppCommands {
     #[command(about = "Show configured applications")]
     List,
 }
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    #[command(about = "Show saved config backups, newest first")]
+    List,
+    #[command(about = "Restore app config files from a backup")]
+    Restore {
+        #[arg(help = "Backup id as shown by `backup list`")]
+        id: String,
+        #[arg(
+            long,
+            help = "Only restore files for this app (config key, e.g. vscode)"
+        )]
+        app: Option<String>,
+    },
+}
@@ -0,0 +1,36 @@
+use crate::backup::BackupStore;
+use crate::config::Config;
+use anyhow::Result;
+
+pub(crate) fn backup_store(config: &Config) -> BackupStore {
+    BackupStore::new(
+        BackupStore::default_root(),
+        config.settings.backup_retention,
+    )
+}
+
+pub fn list_backups(config: &Config) -> Result<()> {
+    let backups = backup_store(config).list()?;
+
+    if backups.is_empty() {
+        println!("No backups found");
+        return Ok(());
+    }
+
+    println!("Backups:");
+    for backup in backups {
+        let theme = backup.theme.as_deref().unwrap_or("-");
+        println!("  {} (before switching to {}):", backup.id, theme);
+        for entry in &backup.files {
+            println!("    {}: {}", entry.app, entry.original.display());
+        }
+    }
+    Ok(())
+}
+
+pub fn restore_backup(config: &Config, id: &str, app: Option<&str>) -> Result<()> {
+    for path in backup_store(config).restore(id, app)? {
+        println!("✓ Restored {}", path.display());
+    }
+    Ok(())
+}
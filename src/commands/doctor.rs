@@ -0,0 +1,39 @@
+use crate::adapter::ThemeAdapter;
+use crate::adapters::AdapterRegistry;
+use crate::config::Config;
+use anyhow::Result;
+
+/// Prints the setup problems of one app; returns how many were found
+async fn check_adapter(adapter: &dyn ThemeAdapter, app_display_name: &str) -> usize {
+    if !adapter.is_available().await {
+        println!("  - {}: Not available", app_display_name);
+        return 0;
+    }
+
+    let problems = adapter.doctor().await;
+    if problems.is_empty() {
+        println!("  ✓ {}", app_display_name);
+    } else {
+        println!("  ✗ {}", app_display_name);
+        for problem in &problems {
+            println!("      {}", problem);
+        }
+    }
+    problems.len()
+}
+
+pub async fn run_doctor(config: &Config) -> Result<()> {
+    let registry = AdapterRegistry::from_config(config);
+
+    println!("Checking app setup:");
+
+    let mut problems = 0;
+    for adapter in registry.get_all_adapters() {
+        problems += check_adapter(adapter.as_ref(), adapter.app_name()).await;
+    }
+
+    if problems > 0 {
+        println!("\n{} problem(s) found", problems);
+    }
+    Ok(())
+}
@@ -6,18 +6,31 @@ use anyhow::Result;
 async fn show_adapter_availability(
     adapter: &dyn ThemeAdapter,
     app_display_name: &str,
+    enabled: bool,
 ) -> Result<()> {
-    let status = if adapter.is_available().await {
+    let status = if !enabled {
+        "Disabled"
+    } else if adapter.is_available().await {
         "Available"
     } else {
         "Not available"
     };
-    println!("  {}: {}", app_display_name, status);
+    let methods: Vec<String> = adapter
+        .supported_methods()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    println!(
+        "  {}: {} (methods: {})",
+        app_display_name,
+        status,
+        methods.join(", ")
+    );
     Ok(())
 }
 
 pub fn list_themes(config: &Config) -> Result<()> {
-    let registry = AdapterRegistry::new();
+    let registry = AdapterRegistry::from_config(config);
 
     println!("Available themes:");
     for (name, mapping) in &config.themes {
@@ -31,13 +44,15 @@ pub fn list_themes(config: &Config) -> Result<()> {
     Ok(())
 }
 
-pub async fn list_apps(_config: &Config) -> Result<()> {
-    let registry = AdapterRegistry::new();
+pub async fn list_apps(config: &Config) -> Result<()> {
+    // Disabled apps are listed too, so they can be told apart from missing ones
+    let registry = AdapterRegistry::with_app_configs(&config.apps);
 
     println!("Available applications:");
 
     for adapter in registry.get_all_adapters() {
-        show_adapter_availability(adapter.as_ref(), adapter.app_name()).await?;
+        let enabled = config.is_app_enabled(adapter.config_key());
+        show_adapter_availability(adapter.as_ref(), adapter.app_name(), enabled).await?;
     }
 
     Ok(())
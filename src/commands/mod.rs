@@ -1,8 +1,12 @@
+pub mod backup;
+pub mod doctor;
 pub mod list;
 pub mod set;
 pub mod status;
 pub mod toggle;
 
+pub use backup::{list_backups, restore_backup};
+pub use doctor::run_doctor;
 pub use list::{list_apps, list_themes};
 pub use set::set_theme;
 pub use status::show_status;
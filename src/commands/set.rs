@@ -1,30 +1,32 @@
-use crate::adapter::ThemeAdapter;
+use super::backup::backup_store;
 use crate::adapters::AdapterRegistry;
+use crate::apply::{
+    apply_theme, apply_theme_atomic, AdapterReport, ApplyOptions, ApplyOutcome, AtomicOutcome,
+};
 use crate::config::Config;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use std::time::Duration;
 
-async fn apply_theme_to_adapter(
-    adapter: &dyn ThemeAdapter,
-    theme_option: Option<&String>,
-    app_display_name: &str,
-) -> Result<()> {
-    if adapter.is_available().await {
-        if let Some(theme) = theme_option {
-            match adapter.set_theme(theme).await {
-                Ok(_) => println!("✓ Set {} theme to: {}", app_display_name, theme),
-                Err(e) => println!("✗ Failed to set {} theme: {}", app_display_name, e),
+fn print_report(report: &AdapterReport) {
+    match &report.outcome {
+        ApplyOutcome::Applied(theme) => {
+            println!("✓ Set {} theme to: {}", report.app_name, theme);
+            if report.needs_restart {
+                println!("  Restart running {} instances to see it", report.app_name);
             }
         }
-    } else {
-        println!("- {} not available", app_display_name);
+        ApplyOutcome::Failed(e) => println!("✗ Failed to set {} theme: {}", report.app_name, e),
+        ApplyOutcome::TimedOut => println!("✗ Timed out setting {} theme", report.app_name),
+        ApplyOutcome::Unavailable => println!("- {} not available", report.app_name),
+        ApplyOutcome::NotMapped => {}
     }
-    Ok(())
 }
 
 pub async fn set_theme(
     config: &mut Config,
-    config_path: &std::path::PathBuf,
+    config_path: &std::path::Path,
     theme_name: &str,
+    atomic: bool,
 ) -> Result<()> {
     if !config.themes.contains_key(theme_name) {
         println!("Error: Theme '{}' not found in configuration", theme_name);
@@ -32,11 +34,56 @@ pub async fn set_theme(
     }
 
     let theme_mapping = &config.themes[theme_name];
-    let registry = AdapterRegistry::new();
+    let registry = AdapterRegistry::from_config(config);
 
-    for adapter in registry.get_all_adapters() {
-        let app_theme = theme_mapping.get(adapter.config_key());
-        apply_theme_to_adapter(adapter.as_ref(), app_theme, adapter.app_name()).await?;
+    if config.settings.backup_configs {
+        let files: Vec<_> = registry
+            .get_all_adapters()
+            .iter()
+            .filter(|adapter| theme_mapping.contains_key(adapter.config_key()))
+            .flat_map(|adapter| {
+                let key = adapter.config_key().to_string();
+                adapter
+                    .config_files()
+                    .into_iter()
+                    .map(move |path| (key.clone(), path))
+            })
+            .collect();
+
+        if let Some(backup) = backup_store(config).create(&files, Some(theme_name))? {
+            println!("Backed up app configs to backup {}", backup.id);
+        }
+    }
+
+    let options = ApplyOptions {
+        parallel: config.settings.parallel_execution,
+        timeout: Duration::from_secs(config.settings.adapter_timeout_secs),
+        methods: config.app_methods()?,
+    };
+    let adapters = registry.get_all_adapters();
+
+    if atomic {
+        match apply_theme_atomic(adapters, theme_mapping, &options).await {
+            AtomicOutcome::Committed(reports) => reports.iter().for_each(print_report),
+            AtomicOutcome::RolledBack { reports, rollbacks } => {
+                reports.iter().for_each(print_report);
+                for rollback in &rollbacks {
+                    match &rollback.result {
+                        Ok(()) => println!("↺ Rolled back {}", rollback.app_name),
+                        Err(e) => println!("✗ Failed to roll back {}: {}", rollback.app_name, e),
+                    }
+                }
+                return Err(anyhow!(
+                    "Theme '{}' was not applied; changed apps were rolled back",
+                    theme_name
+                ));
+            }
+        }
+    } else {
+        apply_theme(adapters, theme_mapping, &options)
+            .await
+            .iter()
+            .for_each(print_report);
     }
 
     // Update theme state and save config
@@ -1,4 +1,4 @@
-use crate::adapter::ThemeAdapter;
+use crate::adapter::{ThemeAdapter, ThemePair};
 use crate::adapters::AdapterRegistry;
 use crate::config::Config;
 use anyhow::Result;
@@ -6,7 +6,13 @@ use anyhow::Result;
 async fn show_adapter_status(adapter: &dyn ThemeAdapter, app_display_name: &str) -> Result<()> {
     if adapter.is_available().await {
         match adapter.get_theme().await {
-            Ok(theme) => println!("  {}: {}", app_display_name, theme),
+            Ok(theme) => match ThemePair::parse(&theme) {
+                Some(pair) => println!(
+                    "  {}: {} (light), {} (dark)",
+                    app_display_name, pair.light, pair.dark
+                ),
+                None => println!("  {}: {}", app_display_name, theme),
+            },
             Err(e) => println!("  {}: Error - {}", app_display_name, e),
         }
     } else {
@@ -15,8 +21,8 @@ async fn show_adapter_status(adapter: &dyn ThemeAdapter, app_display_name: &str)
     Ok(())
 }
 
-pub async fn show_status(_config: &Config) -> Result<()> {
-    let registry = AdapterRegistry::new();
+pub async fn show_status(config: &Config) -> Result<()> {
+    let registry = AdapterRegistry::from_config(config);
 
     println!("Theme Status:");
 
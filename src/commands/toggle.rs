@@ -1,7 +1,7 @@
 use crate::config::Config;
 use anyhow::Result;
 
-pub async fn toggle_theme(config: &mut Config, config_path: &std::path::PathBuf) -> Result<()> {
+pub async fn toggle_theme(config: &mut Config, config_path: &std::path::Path) -> Result<()> {
     // Determine what theme to toggle to
     let target_theme = match (
         &config.settings.current_theme,
@@ -40,6 +40,6 @@ pub async fn toggle_theme(config: &mut Config, config_path: &std::path::PathBuf)
     }
 
     println!("Toggling to theme: {}", target_theme);
-    super::set::set_theme(config, config_path, &target_theme).await?;
+    super::set::set_theme(config, config_path, &target_theme, false).await?;
     Ok(())
 }
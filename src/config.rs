@@ -1,8 +1,10 @@
+use crate::adapter::ApplyMethod;
 use crate::adapters::AdapterRegistry;
-use anyhow::Result;
+use crate::atomic_write::write_atomic;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -16,17 +18,58 @@ pub struct AppConfig {
     pub enabled: bool,
     pub path: Option<PathBuf>,
     pub method: String,
+    /// Directory holding theme files, for apps that switch by pointing at a theme file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub themes_dir: Option<PathBuf>,
+}
+
+impl AppConfig {
+    pub fn apply_method(&self) -> Result<ApplyMethod> {
+        self.method.parse()
+    }
+
+    /// Returns the configured path override with a leading `~/` expanded to the home directory
+    pub fn resolved_path(&self) -> Option<PathBuf> {
+        self.path.as_deref().map(expand_home)
+    }
+
+    /// Returns the configured themes directory with a leading `~/` expanded
+    pub fn resolved_themes_dir(&self) -> Option<PathBuf> {
+        self.themes_dir.as_deref().map(expand_home)
+    }
+}
+
+/// Expands a leading `~` in `path` to the home directory
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub default_theme: String,
     pub backup_configs: bool,
+    /// Number of backups to keep; 0 keeps every backup
+    #[serde(default = "default_backup_retention")]
+    pub backup_retention: usize,
     pub parallel_execution: bool,
+    /// Seconds each adapter may spend detecting and switching before it is abandoned
+    #[serde(default = "default_adapter_timeout_secs")]
+    pub adapter_timeout_secs: u64,
     pub current_theme: Option<String>,
     pub previous_theme: Option<String>,
 }
 
+fn default_backup_retention() -> usize {
+    20
+}
+
+fn default_adapter_timeout_secs() -> u64 {
+    10
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self::new_with_defaults()
@@ -66,6 +109,7 @@ impl Config {
                     enabled: true,
                     path: None, // Let adapters auto-detect their paths
                     method: "auto".to_string(),
+                    themes_dir: None,
                 },
             );
         }
@@ -76,7 +120,9 @@ impl Config {
             settings: Settings {
                 default_theme: "dark".to_string(),
                 backup_configs: true,
+                backup_retention: default_backup_retention(),
                 parallel_execution: true,
+                adapter_timeout_secs: default_adapter_timeout_secs(),
                 current_theme: None,
                 previous_theme: None,
             },
@@ -92,16 +138,30 @@ impl Config {
         Ok(config)
     }
 
-    pub fn save_to_file(&self, path: &PathBuf) -> Result<()> {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
         let contents = serde_yaml::to_string(self)?;
-        std::fs::write(path, contents)?;
+        write_atomic(path, contents)?;
         Ok(())
     }
 
+    /// Apps without an entry in `apps` (e.g. adapters added after the config was written) are enabled
+    pub fn is_app_enabled(&self, config_key: &str) -> bool {
+        self.apps.get(config_key).is_none_or(|app| app.enabled)
+    }
+
+    /// Returns the configured apply method for every app, keyed by config key
+    pub fn app_methods(&self) -> Result<HashMap<String, ApplyMethod>> {
+        self.apps
+            .iter()
+            .map(|(key, app)| {
+                let method = app
+                    .apply_method()
+                    .with_context(|| format!("Invalid method for app '{}'", key))?;
+                Ok((key.clone(), method))
+            })
+            .collect()
+    }
+
     pub fn update_theme_state(&mut self, new_theme: &str) {
         self.settings.previous_theme = self.settings.current_theme.clone();
         self.settings.current_theme = Some(new_theme.to_string());
@@ -0,0 +1,175 @@
+//! Lossless editing of INI-style `key=value` files such as GTK's `settings.ini`.
+//!
+//! `set` rewrites only the value of the line being changed (or inserts a new line at
+//! the end of its section), so comments, blank lines, key order and spacing around
+//! `=` elsewhere in the file are left untouched. Lines starting with `#` or `;` are
+//! comments.
+
+use std::ops::Range;
+
+/// Returns the section name if `line` is a `[section]` header
+fn section_header(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix('[')?
+        .strip_suffix(']')
+        .map(str::trim)
+}
+
+/// Splits a `key=value` line into its key and the byte range of its value
+fn entry(line: &str) -> Option<(&str, Range<usize>)> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') || trimmed.starts_with(';') || section_header(line).is_some() {
+        return None;
+    }
+
+    let eq = line.find('=')?;
+    let value = &line[eq + 1..];
+    let start = eq + 1 + (value.len() - value.trim_start().len());
+    let end = eq + 1 + value.trim_end().len();
+    Some((line[..eq].trim(), start..end.max(start)))
+}
+
+/// A line of the document with its byte offsets
+struct Line<'a> {
+    start: usize,
+    /// Offset just past the line's newline
+    end: usize,
+    text: &'a str,
+    /// Section the line belongs to; a header belongs to the section it opens
+    section: &'a str,
+}
+
+fn lines(src: &str) -> Vec<Line<'_>> {
+    let mut section = "";
+    let mut start = 0;
+    src.split_inclusive('\n')
+        .map(|raw| {
+            let text = raw.trim_end_matches(['\n', '\r']);
+            if let Some(name) = section_header(text) {
+                section = name;
+            }
+            let line = Line {
+                start,
+                end: start + raw.len(),
+                text,
+                section,
+            };
+            start = line.end;
+            line
+        })
+        .collect()
+}
+
+/// Returns the value of `key` in `section`; if the key repeats, the last one wins
+///
+/// Keys before the first section header belong to the section named `""`.
+pub fn get(src: &str, section: &str, key: &str) -> Option<String> {
+    entries(src, section)
+        .into_iter()
+        .rev()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value)
+}
+
+/// Returns the `key=value` entries of `section` in file order
+pub fn entries(src: &str, section: &str) -> Vec<(String, String)> {
+    lines(src)
+        .iter()
+        .filter(|line| line.section == section)
+        .filter_map(|line| {
+            let (key, value) = entry(line.text)?;
+            Some((key.to_string(), line.text[value].to_string()))
+        })
+        .collect()
+}
+
+/// Returns the names of all sections with a header, in file order
+pub fn sections(src: &str) -> Vec<String> {
+    lines(src)
+        .iter()
+        .filter_map(|line| section_header(line.text))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Removes every `key=value` entry of `section`, keeping its header and comments
+pub fn clear_section(src: &str, section: &str) -> String {
+    lines(src)
+        .iter()
+        .filter(|line| line.section != section || entry(line.text).is_none())
+        .map(|line| &src[line.start..line.end])
+        .collect()
+}
+
+/// Sets `key` in `section` to `value`
+///
+/// The last existing line for `key` is rewritten in place. A missing key is added
+/// after the last entry of its section, and a missing section is appended to the end
+/// of the document.
+pub fn set(src: &str, section: &str, key: &str, value: &str) -> String {
+    set_where(src, section, key, value, |_| true)
+}
+
+/// Like `set`, but only rewrites a line for `key` whose current value satisfies
+/// `predicate`, so repeatable keys like foot's `include` keep their other lines
+pub fn set_where(
+    src: &str,
+    section: &str,
+    key: &str,
+    value: &str,
+    predicate: impl Fn(&str) -> bool,
+) -> String {
+    let lines = lines(src);
+    let in_section: Vec<&Line> = lines
+        .iter()
+        .filter(|line| line.section == section)
+        .collect();
+
+    let existing = in_section
+        .iter()
+        .rev()
+        .find_map(|line| match entry(line.text) {
+            Some((name, range)) if name == key && predicate(&line.text[range.clone()]) => {
+                Some((line, range))
+            }
+            _ => None,
+        });
+    if let Some((line, range)) = existing {
+        let at = line.start + range.start..line.start + range.end;
+        return format!("{}{}{}", &src[..at.start], value, &src[at.end..]);
+    }
+
+    let new_line = format!("{}={}\n", key, value);
+    // Insert after the last non-blank line of the section, keeping blank lines that
+    // separate it from the next one
+    let anchor = in_section
+        .iter()
+        .rev()
+        .find(|line| !line.text.trim().is_empty());
+    match anchor {
+        Some(line) => {
+            let separator = if src[..line.end].ends_with('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            format!(
+                "{}{}{}{}",
+                &src[..line.end],
+                separator,
+                new_line,
+                &src[line.end..]
+            )
+        }
+        None if section.is_empty() => format!("{}{}", new_line, src),
+        None => {
+            let separator = match src {
+                "" => "",
+                _ if src.ends_with("\n\n") => "",
+                _ if src.ends_with('\n') => "\n",
+                _ => "\n\n",
+            };
+            format!("{}{}[{}]\n{}", src, separator, section, new_line)
+        }
+    }
+}
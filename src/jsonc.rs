@@ -0,0 +1,405 @@
+//! Lossless editing of JSON-with-comments files such as VS Code's `settings.json`.
+//!
+//! `parse` accepts `//` and `/* */` comments and trailing commas. `set_value` rewrites
+//! only the bytes of the value being changed (or inserts a new member), so comments,
+//! key order and indentation elsewhere in the file are left untouched. A leading UTF-8
+//! byte order mark, which Windows tools like to add, is skipped and kept on write.
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+const BOM: char = '\u{feff}';
+
+/// Parses a JSONC document into a `serde_json::Value`
+///
+/// An empty or whitespace-only document parses as an empty object.
+pub fn parse(src: &str) -> Result<Value> {
+    let src = src.strip_prefix(BOM).unwrap_or(src);
+    if src.trim().is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
+    Ok(Parser::new(src).parse_document()?.value)
+}
+
+/// Sets the member at `path` (a list of nested object keys) to `value`
+///
+/// Missing objects along the path are created. Only the replaced value, or the newly
+/// inserted member, differs from `src` in the returned document.
+pub fn set_value(src: &str, path: &[&str], value: &Value) -> Result<String> {
+    if path.is_empty() {
+        return Err(anyhow!("JSON path must not be empty"));
+    }
+
+    if let Some(rest) = src.strip_prefix(BOM) {
+        return Ok(format!("{}{}", BOM, set_value(rest, path, value)?));
+    }
+
+    if src.trim().is_empty() {
+        return Ok(format!(
+            "{}\n",
+            to_string_indented(&nest(path, value), "    ", "", "\n")
+        ));
+    }
+
+    let root = Parser::new(src).parse_document()?;
+    let unit = detect_indent_unit(src, &root);
+
+    let mut node = &root;
+    for (index, segment) in path.iter().enumerate() {
+        let members = node
+            .members
+            .as_ref()
+            .ok_or_else(|| anyhow!("Expected a JSON object at '{}'", path[..index].join(".")))?;
+
+        let remaining = &path[index + 1..];
+        // Duplicate keys are legal in JSONC; the last one wins, as in VS Code
+        match members.iter().rev().find(|member| member.key == *segment) {
+            Some(member) if remaining.is_empty() || member.value.members.is_none() => {
+                let indent = line_indent(src, member.key_start);
+                let replacement =
+                    to_string_indented(&nest(remaining, value), unit, &indent, newline_style(src));
+                return Ok(splice(
+                    src,
+                    member.value.start,
+                    member.value.end,
+                    &replacement,
+                ));
+            }
+            Some(member) => node = &member.value,
+            None => {
+                let new_value = nest(remaining, value);
+                return Ok(insert_member(src, node, segment, &new_value, unit));
+            }
+        }
+    }
+
+    unreachable!("path always ends in a replacement or insertion")
+}
+
+/// Wraps `value` in one object per key in `path`, innermost last
+fn nest(path: &[&str], value: &Value) -> Value {
+    path.iter().rev().fold(value.clone(), |inner, key| {
+        let mut map = Map::new();
+        map.insert(key.to_string(), inner);
+        Value::Object(map)
+    })
+}
+
+fn insert_member(src: &str, object: &Node, key: &str, value: &Value, unit: &str) -> String {
+    let members = object.members.as_deref().unwrap_or_default();
+    let object_indent = line_indent(src, object.start);
+    let indent = match members.first() {
+        Some(member) => line_indent(src, member.key_start),
+        None => format!("{}{}", object_indent, unit),
+    };
+    let newline = newline_style(src);
+    let member_text = format!(
+        "{}: {}",
+        Value::String(key.to_string()),
+        to_string_indented(value, unit, &indent, newline)
+    );
+
+    let Some(last) = members.last() else {
+        // Insert right after the opening brace, keeping any comments inside the object
+        let inner = &src[object.start + 1..object.end - 1];
+        let closing = if inner.contains('\n') {
+            String::new()
+        } else {
+            format!("{}{}", newline, object_indent)
+        };
+        let text = format!("{}{}{}{}", newline, indent, member_text, closing);
+        return splice(src, object.start + 1, object.start + 1, &text);
+    };
+
+    // Keep a trailing `// comment` on the last member's line attached to that member
+    let line_end = src[last.value.end..]
+        .find(newline)
+        .map_or(src.len(), |i| last.value.end + i);
+    let rest = src[last.value.end..line_end].trim();
+    let has_comma = rest.starts_with(',');
+    let rest = rest.trim_start_matches(',').trim_start();
+    let insert_at = if rest.is_empty() || rest.starts_with("//") {
+        line_end
+    } else {
+        last.value.end
+    };
+
+    let comma = if has_comma { "" } else { "," };
+    if insert_at == last.value.end {
+        let text = format!("{}{}{}{}", comma, newline, indent, member_text);
+        return splice(src, insert_at, insert_at, &text);
+    }
+
+    let text = format!("{}{}{}", newline, indent, member_text);
+    let with_member = splice(src, insert_at, insert_at, &text);
+    splice(&with_member, last.value.end, last.value.end, comma)
+}
+
+fn splice(src: &str, start: usize, end: usize, replacement: &str) -> String {
+    let mut out = String::with_capacity(src.len() + replacement.len());
+    out.push_str(&src[..start]);
+    out.push_str(replacement);
+    out.push_str(&src[end..]);
+    out
+}
+
+/// Returns the leading whitespace of the line containing byte offset `pos`
+fn line_indent(src: &str, pos: usize) -> String {
+    let line_start = src[..pos].rfind('\n').map_or(0, |i| i + 1);
+    src[line_start..pos]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+fn detect_indent_unit<'a>(src: &'a str, root: &Node) -> &'a str {
+    let first_key = root
+        .members
+        .as_ref()
+        .and_then(|members| members.first())
+        .map(|member| member.key_start);
+
+    match first_key {
+        Some(pos) => {
+            let line_start = src[..pos].rfind('\n').map_or(0, |i| i + 1);
+            let indent = &src[line_start..pos];
+            if !indent.is_empty() && indent.trim().is_empty() {
+                indent
+            } else {
+                "    "
+            }
+        }
+        None => "    ",
+    }
+}
+
+// Files written on Windows (e.g. Windows Terminal's settings.json) use CRLF
+fn newline_style(src: &str) -> &'static str {
+    if src.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Serializes `value`, indenting nested lines with `unit` relative to `base`
+fn to_string_indented(value: &Value, unit: &str, base: &str, newline: &str) -> String {
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    serde::Serialize::serialize(value, &mut serializer).expect("serializing a Value cannot fail");
+
+    String::from_utf8(buf)
+        .expect("serde_json emits UTF-8")
+        .replace('\n', &format!("{}{}", newline, base))
+}
+
+struct Node {
+    start: usize,
+    end: usize,
+    value: Value,
+    /// Set for objects only
+    members: Option<Vec<Member>>,
+}
+
+struct Member {
+    key: String,
+    key_start: usize,
+    value: Node,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            bytes: src.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Node> {
+        self.skip_trivia()?;
+        let node = self.parse_value()?;
+        self.skip_trivia()?;
+        if self.pos < self.bytes.len() {
+            return Err(self.error("Unexpected content after JSON value"));
+        }
+        Ok(node)
+    }
+
+    fn parse_value(&mut self) -> Result<Node> {
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => {
+                let start = self.pos;
+                self.skip_string()?;
+                self.scalar(start)
+            }
+            Some(_) => {
+                let start = self.pos;
+                while let Some(b) = self.peek() {
+                    if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.') {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if start == self.pos {
+                    return Err(self.error("Expected a JSON value"));
+                }
+                self.scalar(start)
+            }
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn scalar(&self, start: usize) -> Result<Node> {
+        let raw = &self.src[start..self.pos];
+        let value = serde_json::from_str(raw)
+            .map_err(|e| anyhow!("Invalid JSON value at offset {}: {}", start, e))?;
+        Ok(Node {
+            start,
+            end: self.pos,
+            value,
+            members: None,
+        })
+    }
+
+    fn parse_object(&mut self) -> Result<Node> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut members = Vec::new();
+        let mut map = Map::new();
+
+        loop {
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(b'}') => break,
+                Some(b'"') => {}
+                _ => return Err(self.error("Expected a string key or '}'")),
+            }
+
+            let key_start = self.pos;
+            self.skip_string()?;
+            let key: String = serde_json::from_str(&self.src[key_start..self.pos])
+                .map_err(|e| anyhow!("Invalid key at offset {}: {}", key_start, e))?;
+
+            self.skip_trivia()?;
+            self.expect(b':')?;
+            self.skip_trivia()?;
+            let value = self.parse_value()?;
+            map.insert(key.clone(), value.value.clone());
+            members.push(Member {
+                key,
+                key_start,
+                value,
+            });
+
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => break,
+                _ => return Err(self.error("Expected ',' or '}'")),
+            }
+        }
+
+        self.pos += 1;
+        Ok(Node {
+            start,
+            end: self.pos,
+            value: Value::Object(map),
+            members: Some(members),
+        })
+    }
+
+    fn parse_array(&mut self) -> Result<Node> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_trivia()?;
+            if self.peek() == Some(b']') {
+                break;
+            }
+
+            items.push(self.parse_value()?.value);
+
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => break,
+                _ => return Err(self.error("Expected ',' or ']'")),
+            }
+        }
+
+        self.pos += 1;
+        Ok(Node {
+            start,
+            end: self.pos,
+            value: Value::Array(items),
+            members: None,
+        })
+    }
+
+    fn skip_string(&mut self) -> Result<()> {
+        self.pos += 1;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'\\' => self.pos += 1,
+                b'"' => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(self.error("Unterminated string"))
+    }
+
+    fn skip_trivia(&mut self) -> Result<()> {
+        loop {
+            match (self.peek(), self.bytes.get(self.pos + 1)) {
+                (Some(b' ' | b'\t' | b'\n' | b'\r'), _) => self.pos += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.pos += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    let end = self.src[self.pos + 2..]
+                        .find("*/")
+                        .ok_or_else(|| self.error("Unterminated block comment"))?;
+                    self.pos += 2 + end + 2;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", byte as char)))
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        let line = self.src[..self.pos.min(self.src.len())]
+            .matches('\n')
+            .count()
+            + 1;
+        anyhow!("{} at line {}", message, line)
+    }
+}
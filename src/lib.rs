@@ -1,10 +1,24 @@
 pub mod adapter;
 pub mod adapters;
+pub mod appearance;
+pub mod apply;
+pub mod atomic_write;
+pub mod backup;
 pub mod config;
+pub mod ini;
+pub mod jsonc;
+pub mod lua;
+pub mod runner;
+pub mod xml;
 
 pub use adapter::*;
 pub use adapters::*;
+pub use appearance::*;
+pub use apply::*;
+pub use atomic_write::*;
+pub use backup::*;
 pub use config::*;
+pub use runner::*;
 
 #[cfg(test)]
 mod tests {
@@ -94,13 +108,2053 @@ mod tests {
         let registry = AdapterRegistry::new();
         let adapters = registry.get_all_adapters();
 
-        // Should have all three adapters
-        assert_eq!(adapters.len(), 3);
+        // Should have all twenty-three adapters, plus any VS Code profiles and JetBrains
+        // IDEs on this machine
+        let config_keys: Vec<&str> = adapters
+            .iter()
+            .map(|a| a.config_key())
+            .filter(|key| !key.contains("-profile-") && !key.starts_with("jetbrains-"))
+            .collect();
+        assert_eq!(config_keys.len(), 23);
 
         // Test that we have adapters with expected config keys
-        let config_keys: Vec<&str> = adapters.iter().map(|a| a.config_key()).collect();
         assert!(config_keys.contains(&"vscode"));
+        assert!(config_keys.contains(&"vscodium"));
+        assert!(config_keys.contains(&"vscode-insiders"));
+        assert!(config_keys.contains(&"code-oss"));
+        assert!(config_keys.contains(&"cursor"));
+        assert!(config_keys.contains(&"vscode-flatpak"));
         assert!(config_keys.contains(&"helix"));
         assert!(config_keys.contains(&"ghostty"));
+        assert!(config_keys.contains(&"alacritty"));
+        assert!(config_keys.contains(&"kitty"));
+        assert!(config_keys.contains(&"wezterm"));
+        assert!(config_keys.contains(&"neovim"));
+        assert!(config_keys.contains(&"zed"));
+        assert!(config_keys.contains(&"tmux"));
+        assert!(config_keys.contains(&"emacs"));
+        assert!(config_keys.contains(&"sublime-text"));
+        assert!(config_keys.contains(&"sublime-merge"));
+        assert!(config_keys.contains(&"gtk"));
+        assert!(config_keys.contains(&"kde"));
+        assert!(config_keys.contains(&"windows-terminal"));
+        assert!(config_keys.contains(&"foot"));
+        assert!(config_keys.contains(&"konsole"));
+        assert!(config_keys.contains(&"gnome-terminal"));
+    }
+
+    #[test]
+    fn test_backup_create_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = dir.path().join("settings.json");
+        std::fs::write(&settings, "{\"original\": true}").unwrap();
+
+        let store = BackupStore::new(dir.path().join("backups"), 0);
+        let files = vec![
+            ("vscode".to_string(), settings.clone()),
+            ("helix".to_string(), dir.path().join("missing.toml")),
+        ];
+        let backup = store.create(&files, Some("dark")).unwrap().unwrap();
+
+        // Missing files are skipped rather than failing the backup
+        assert_eq!(backup.files.len(), 1);
+        assert_eq!(backup.theme.as_deref(), Some("dark"));
+
+        std::fs::write(&settings, "{}").unwrap();
+        let restored = store.restore(&backup.id, Some("vscode")).unwrap();
+        assert_eq!(restored, vec![settings.clone()]);
+        assert_eq!(
+            std::fs::read_to_string(&settings).unwrap(),
+            "{\"original\": true}"
+        );
+
+        assert!(store.restore(&backup.id, Some("helix")).is_err());
+        assert!(store.restore("unknown", None).is_err());
+    }
+
+    #[test]
+    fn test_backup_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        std::fs::write(&config, "theme = dark").unwrap();
+
+        let store = BackupStore::new(dir.path().join("backups"), 2);
+        let files = vec![("ghostty".to_string(), config)];
+        let ids: Vec<_> = (0..3)
+            .map(|_| store.create(&files, None).unwrap().unwrap().id)
+            .collect();
+
+        let remaining: Vec<_> = store.list().unwrap().into_iter().map(|b| b.id).collect();
+        assert_eq!(remaining, vec![ids[2].clone(), ids[1].clone()]);
+    }
+
+    #[test]
+    fn test_backup_skips_corrupt_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        std::fs::write(&config, "theme = dark").unwrap();
+
+        let root = dir.path().join("backups");
+        std::fs::create_dir_all(root.join("00000000-000000-000")).unwrap();
+        std::fs::write(
+            root.join("00000000-000000-000").join("manifest.yaml"),
+            "id: [unterminated",
+        )
+        .unwrap();
+
+        // Pruning lists every backup, so a corrupt manifest must not fail later backups
+        let store = BackupStore::new(root, 1);
+        let files = vec![("ghostty".to_string(), config)];
+        let backup = store.create(&files, None).unwrap().unwrap();
+
+        let ids: Vec<_> = store.list().unwrap().into_iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![backup.id]);
+    }
+
+    #[test]
+    fn test_backup_skipped_without_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BackupStore::new(dir.path().join("backups"), 0);
+        let files = vec![("vscode".to_string(), dir.path().join("missing.json"))];
+
+        assert!(store.create(&files, None).unwrap().is_none());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    struct FakeAdapter {
+        key: &'static str,
+        delay: std::time::Duration,
+        fail: bool,
+        file: Option<std::path::PathBuf>,
+    }
+
+    #[async_trait::async_trait]
+    impl ThemeAdapter for FakeAdapter {
+        async fn set_theme(&self, theme: &str) -> Result<(), ThemeError> {
+            tokio::time::sleep(self.delay).await;
+            if let Some(file) = &self.file {
+                std::fs::write(file, theme).unwrap();
+            }
+            if self.fail {
+                return Err(ThemeError {
+                    message: "boom".to_string(),
+                    app_name: self.key.to_string(),
+                });
+            }
+            Ok(())
+        }
+
+        async fn get_theme(&self) -> Result<String, ThemeError> {
+            Ok("default".to_string())
+        }
+
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        fn app_name(&self) -> &str {
+            self.key
+        }
+
+        fn config_key(&self) -> &str {
+            self.key
+        }
+
+        fn default_themes(&self) -> std::collections::HashMap<String, String> {
+            std::collections::HashMap::new()
+        }
+
+        fn config_files(&self) -> Vec<std::path::PathBuf> {
+            self.file.iter().cloned().collect()
+        }
+    }
+
+    fn fake(key: &'static str, delay_ms: u64, fail: bool) -> std::sync::Arc<dyn ThemeAdapter> {
+        std::sync::Arc::new(FakeAdapter {
+            key,
+            delay: std::time::Duration::from_millis(delay_ms),
+            fail,
+            file: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_parallel_apply_keeps_adapter_order() {
+        let adapters = vec![
+            fake("slow", 50, false),
+            fake("fast", 0, true),
+            fake("unmapped", 0, false),
+        ];
+        let mapping = [("slow", "a"), ("fast", "b")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = ApplyOptions {
+            parallel: true,
+            timeout: std::time::Duration::from_secs(5),
+            methods: std::collections::HashMap::new(),
+        };
+
+        let reports = apply_theme(&adapters, &mapping, &options).await;
+        let names: Vec<_> = reports.iter().map(|r| r.app_name.as_str()).collect();
+        assert_eq!(names, vec!["slow", "fast", "unmapped"]);
+        assert!(matches!(&reports[0].outcome, ApplyOutcome::Applied(theme) if theme == "a"));
+        assert!(matches!(reports[1].outcome, ApplyOutcome::Failed(_)));
+        assert!(matches!(reports[2].outcome, ApplyOutcome::NotMapped));
+    }
+
+    #[tokio::test]
+    async fn test_apply_times_out_hung_adapter() {
+        let adapters = vec![fake("hung", 10_000, false), fake("ok", 0, false)];
+        let mapping = [("hung", "a"), ("ok", "b")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        for parallel in [true, false] {
+            let options = ApplyOptions {
+                parallel,
+                timeout: std::time::Duration::from_millis(50),
+                methods: std::collections::HashMap::new(),
+            };
+            let reports = apply_theme(&adapters, &mapping, &options).await;
+            assert!(matches!(reports[0].outcome, ApplyOutcome::TimedOut));
+            assert!(matches!(reports[1].outcome, ApplyOutcome::Applied(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_atomic_apply_rolls_back_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("existing.conf");
+        let created = dir.path().join("created.conf");
+        std::fs::write(&existing, "original").unwrap();
+
+        let file_adapter =
+            |key, file: &std::path::Path, fail| -> std::sync::Arc<dyn ThemeAdapter> {
+                std::sync::Arc::new(FakeAdapter {
+                    key,
+                    delay: std::time::Duration::ZERO,
+                    fail,
+                    file: Some(file.to_path_buf()),
+                })
+            };
+        let adapters = vec![
+            file_adapter("existing", &existing, false),
+            file_adapter("created", &created, true),
+        ];
+        let mapping = [("existing", "dark"), ("created", "dark")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = ApplyOptions {
+            parallel: false,
+            timeout: std::time::Duration::from_secs(5),
+            methods: std::collections::HashMap::new(),
+        };
+
+        let outcome = apply_theme_atomic(&adapters, &mapping, &options).await;
+        match outcome {
+            AtomicOutcome::RolledBack { rollbacks, .. } => {
+                assert_eq!(rollbacks.len(), 2);
+                assert!(rollbacks.iter().all(|r| r.result.is_ok()));
+            }
+            AtomicOutcome::Committed(_) => panic!("expected rollback"),
+        }
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "original");
+        assert!(!created.exists());
+    }
+
+    #[tokio::test]
+    async fn test_atomic_apply_commits_on_success() {
+        let adapters = vec![fake("a", 0, false), fake("b", 0, false)];
+        let mapping = [("a", "dark")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = ApplyOptions {
+            parallel: true,
+            timeout: std::time::Duration::from_secs(5),
+            methods: std::collections::HashMap::new(),
+        };
+
+        let outcome = apply_theme_atomic(&adapters, &mapping, &options).await;
+        assert!(matches!(outcome, AtomicOutcome::Committed(reports) if reports.len() == 2));
+    }
+
+    /// Adapter whose current theme can't be read, like GNOME Terminal without dconf
+    struct UnreadableAdapter {
+        key: &'static str,
+        available: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl ThemeAdapter for UnreadableAdapter {
+        async fn set_theme(&self, _theme: &str) -> Result<(), ThemeError> {
+            Ok(())
+        }
+
+        async fn get_theme(&self) -> Result<String, ThemeError> {
+            Err(ThemeError {
+                message: "Failed to run dconf".to_string(),
+                app_name: self.key.to_string(),
+            })
+        }
+
+        async fn is_available(&self) -> bool {
+            self.available
+        }
+
+        fn app_name(&self) -> &str {
+            self.key
+        }
+
+        fn config_key(&self) -> &str {
+            self.key
+        }
+
+        fn default_themes(&self) -> std::collections::HashMap<String, String> {
+            std::collections::HashMap::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_atomic_apply_isolates_snapshot_failures() {
+        let unreadable = |key, available| -> std::sync::Arc<dyn ThemeAdapter> {
+            std::sync::Arc::new(UnreadableAdapter { key, available })
+        };
+        let mapping: std::collections::HashMap<String, String> =
+            [("a", "dark"), ("missing", "dark"), ("broken", "dark")]
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        let options = ApplyOptions {
+            parallel: false,
+            timeout: std::time::Duration::from_secs(5),
+            methods: std::collections::HashMap::new(),
+        };
+
+        // Apps that aren't installed are never snapshotted
+        let adapters = vec![fake("a", 0, false), unreadable("missing", false)];
+        let outcome = apply_theme_atomic(&adapters, &mapping, &options).await;
+        match outcome {
+            AtomicOutcome::Committed(reports) => {
+                assert!(matches!(reports[0].outcome, ApplyOutcome::Applied(_)));
+                assert!(matches!(reports[1].outcome, ApplyOutcome::Unavailable));
+            }
+            AtomicOutcome::RolledBack { .. } => panic!("expected commit"),
+        }
+
+        // A failed snapshot fails only that app, which is never switched
+        let adapters = vec![fake("a", 0, false), unreadable("broken", true)];
+        let outcome = apply_theme_atomic(&adapters, &mapping, &options).await;
+        match outcome {
+            AtomicOutcome::RolledBack { reports, rollbacks } => {
+                assert!(matches!(reports[0].outcome, ApplyOutcome::Applied(_)));
+                assert!(
+                    matches!(&reports[1].outcome, ApplyOutcome::Failed(e) if e.app_name == "broken")
+                );
+                let rolled_back: Vec<&str> =
+                    rollbacks.iter().map(|r| r.app_name.as_str()).collect();
+                assert_eq!(rolled_back, vec!["a"]);
+            }
+            AtomicOutcome::Committed(_) => panic!("expected rollback"),
+        }
+    }
+
+    /// Adapter whose `is_available` takes `delay` and counts how often it is asked
+    struct SlowProbeAdapter {
+        delay: std::time::Duration,
+        probes: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ThemeAdapter for SlowProbeAdapter {
+        async fn set_theme(&self, _theme: &str) -> Result<(), ThemeError> {
+            Ok(())
+        }
+
+        async fn get_theme(&self) -> Result<String, ThemeError> {
+            Ok("default".to_string())
+        }
+
+        async fn is_available(&self) -> bool {
+            self.probes
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            true
+        }
+
+        fn app_name(&self) -> &str {
+            "slow"
+        }
+
+        fn config_key(&self) -> &str {
+            "slow"
+        }
+
+        fn default_themes(&self) -> std::collections::HashMap<String, String> {
+            std::collections::HashMap::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_atomic_apply_times_out_availability_checks() {
+        let mapping: std::collections::HashMap<String, String> = [("a", "dark"), ("slow", "dark")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = ApplyOptions {
+            parallel: false,
+            timeout: std::time::Duration::from_millis(100),
+            methods: std::collections::HashMap::new(),
+        };
+
+        // A hanging probe is cut off like a hanging switch
+        let slow = std::sync::Arc::new(SlowProbeAdapter {
+            delay: std::time::Duration::from_secs(10),
+            probes: Default::default(),
+        });
+        let adapters: Vec<std::sync::Arc<dyn ThemeAdapter>> = vec![fake("a", 0, false), slow];
+        let started = std::time::Instant::now();
+        let outcome = apply_theme_atomic(&adapters, &mapping, &options).await;
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        match outcome {
+            AtomicOutcome::RolledBack { reports, .. } => {
+                assert!(matches!(reports[1].outcome, ApplyOutcome::TimedOut));
+            }
+            AtomicOutcome::Committed(_) => panic!("expected rollback"),
+        }
+
+        // Availability is checked once per switch
+        let slow = std::sync::Arc::new(SlowProbeAdapter {
+            delay: std::time::Duration::ZERO,
+            probes: Default::default(),
+        });
+        let adapters: Vec<std::sync::Arc<dyn ThemeAdapter>> = vec![slow.clone()];
+        let outcome = apply_theme_atomic(&adapters, &mapping, &options).await;
+        assert!(matches!(outcome, AtomicOutcome::Committed(_)));
+        assert_eq!(slow.probes.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_registry_from_config() {
+        let mut config = Config::default();
+        config.apps.get_mut("vscode").unwrap().enabled = false;
+        config.apps.get_mut("helix").unwrap().path =
+            Some(std::path::PathBuf::from("/dotfiles/helix/config.toml"));
+        // Apps missing from the config (e.g. newly added adapters) stay enabled
+        config.apps.remove("ghostty");
+
+        let registry = AdapterRegistry::from_config(&config);
+        let adapters = registry.get_all_adapters();
+        let config_keys: Vec<&str> = adapters.iter().map(|a| a.config_key()).collect();
+        assert!(!config_keys.contains(&"vscode"));
+        assert!(config_keys.contains(&"ghostty"));
+
+        let helix = adapters.iter().find(|a| a.config_key() == "helix").unwrap();
+        assert_eq!(
+            helix.config_files(),
+            vec![std::path::PathBuf::from("/dotfiles/helix/config.toml")]
+        );
+    }
+
+    #[test]
+    fn test_app_config_resolves_home_path() {
+        let app = AppConfig {
+            enabled: true,
+            path: Some(std::path::PathBuf::from("~/dotfiles/ghostty/config")),
+            method: "auto".to_string(),
+            themes_dir: None,
+        };
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            app.resolved_path(),
+            Some(home.join("dotfiles/ghostty/config"))
+        );
+    }
+
+    #[test]
+    fn test_apply_method_parsing() {
+        assert_eq!("auto".parse::<ApplyMethod>().unwrap(), ApplyMethod::Auto);
+        assert_eq!("ipc".parse::<ApplyMethod>().unwrap(), ApplyMethod::Ipc);
+        assert!("telepathy".parse::<ApplyMethod>().is_err());
+
+        let mut config = Config::default();
+        assert!(config
+            .app_methods()
+            .unwrap()
+            .values()
+            .all(|m| *m == ApplyMethod::Auto));
+
+        config.apps.get_mut("helix").unwrap().method = "telepathy".to_string();
+        assert!(config.app_methods().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_method_fails() {
+        let adapters = vec![fake("file-only", 0, false)];
+        let mapping = [("file-only", "dark")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = ApplyOptions {
+            parallel: false,
+            timeout: std::time::Duration::from_secs(5),
+            methods: [("file-only".to_string(), ApplyMethod::Ipc)].into(),
+        };
+
+        let reports = apply_theme(&adapters, &mapping, &options).await;
+        assert!(matches!(reports[0].outcome, ApplyOutcome::Failed(_)));
+    }
+
+    #[test]
+    fn test_jsonc_parse_allows_comments_and_trailing_commas() {
+        let src = "// user settings\n{\n    /* font */ \"editor.fontSize\": 14,\n    \"files.exclude\": {\"**/.git\": true,},\n}\n";
+        let value = jsonc::parse(src).unwrap();
+        assert_eq!(value["editor.fontSize"], 14);
+        assert_eq!(value["files.exclude"]["**/.git"], true);
+        assert!(jsonc::parse("{\"a\": }").is_err());
+    }
+
+    #[test]
+    fn test_vscode_update_setting_replaces_value_in_place() {
+        let src = "{\n    // Theme picked by themesync\n    \"workbench.colorTheme\": \"Dracula\", // dark\n    \"editor.fontSize\": 14,\n}\n";
+        let updated = VsCodeAdapter::update_setting(
+            src,
+            "workbench.colorTheme",
+            serde_json::Value::String("GitHub Light".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(updated, src.replace("\"Dracula\"", "\"GitHub Light\""));
+    }
+
+    #[test]
+    fn test_vscode_update_setting_inserts_missing_key() {
+        let src = "{\n\t\"editor.fontSize\": 14 // keep me\n}\n";
+        let updated = VsCodeAdapter::update_setting(
+            src,
+            "workbench.colorTheme",
+            serde_json::Value::String("Dracula".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            updated,
+            "{\n\t\"editor.fontSize\": 14, // keep me\n\t\"workbench.colorTheme\": \"Dracula\"\n}\n"
+        );
+
+        let empty = VsCodeAdapter::update_setting(
+            "{}",
+            "workbench.colorTheme",
+            serde_json::Value::String("Dracula".to_string()),
+        )
+        .unwrap();
+        assert_eq!(empty, "{\n    \"workbench.colorTheme\": \"Dracula\"\n}");
+    }
+
+    #[test]
+    fn test_jsonc_set_nested_value() {
+        let src = "{\r\n  \"profiles\": {\r\n    \"list\": []\r\n  }\r\n}\r\n";
+        let value = serde_json::Value::String("One Half Dark".to_string());
+        let updated =
+            jsonc::set_value(src, &["profiles", "defaults", "colorScheme"], &value).unwrap();
+
+        assert_eq!(
+            updated,
+            "{\r\n  \"profiles\": {\r\n    \"list\": [],\r\n    \"defaults\": {\r\n      \"colorScheme\": \"One Half Dark\"\r\n    }\r\n  }\r\n}\r\n"
+        );
+        assert_eq!(
+            jsonc::parse(&updated).unwrap()["profiles"]["defaults"]["colorScheme"],
+            "One Half Dark"
+        );
+    }
+
+    #[test]
+    fn test_jsonc_keeps_byte_order_mark() {
+        let src = "\u{feff}{\n    \"editor.fontSize\": 14\n}\n";
+        assert_eq!(jsonc::parse(src).unwrap()["editor.fontSize"], 14);
+
+        let value = serde_json::Value::String("Dracula".to_string());
+        let updated = jsonc::set_value(src, &["workbench.colorTheme"], &value).unwrap();
+        assert_eq!(
+            updated,
+            "\u{feff}{\n    \"editor.fontSize\": 14,\n    \"workbench.colorTheme\": \"Dracula\"\n}\n"
+        );
+    }
+
+    const HELIX_CONFIG: &str = r#"# Helix config, managed by hand
+theme = "onedark" # switched by themesync
+
+[editor]
+line-number = "relative"   # aligned comment
+cursor-shape = { insert = "bar", normal = "block" }
+
+[keys.normal]
+# save with ctrl-s
+C-s = ":w"
+"#;
+
+    #[test]
+    fn test_helix_theme_update_preserves_document() {
+        let updated = HelixAdapter::update_theme_in_config(HELIX_CONFIG, "ayu_light").unwrap();
+
+        assert_eq!(
+            updated,
+            HELIX_CONFIG.replace("\"onedark\"", "\"ayu_light\"")
+        );
+        assert_eq!(
+            HelixAdapter::extract_theme_from_config(&updated).unwrap(),
+            Some("ayu_light".to_string())
+        );
+    }
+
+    #[test]
+    fn test_helix_theme_insert_keeps_tables_intact() {
+        let config = HELIX_CONFIG.replace("theme = \"onedark\" # switched by themesync\n", "");
+        let updated = HelixAdapter::update_theme_in_config(&config, "onedark").unwrap();
+
+        // The new key lands in the root table, before [editor], and nothing else moves
+        let (head, tables) = updated.split_once("[editor]").unwrap();
+        assert!(head.contains("theme = \"onedark\""));
+        assert_eq!(tables, config.split_once("[editor]").unwrap().1);
+
+        assert_eq!(
+            HelixAdapter::update_theme_in_config("", "onedark").unwrap(),
+            "theme = \"onedark\"\n"
+        );
+        assert!(HelixAdapter::update_theme_in_config("theme = ", "onedark").is_err());
+    }
+
+    #[test]
+    fn test_ghostty_theme_update_preserves_lines() {
+        let config = "# Fonts\nfont-size = 17\nfont-feature = -calt\nfont-feature = -liga\n\ntheme = tokyonight\n\n# Keys\nkeybind = ctrl+a=select_all\nkeybind = ctrl+c=copy_to_clipboard\n";
+        let updated = GhosttyAdapter::update_theme_in_config(config, "catppuccin-latte");
+
+        assert_eq!(
+            updated,
+            config.replace("theme = tokyonight", "theme = catppuccin-latte")
+        );
+    }
+
+    #[test]
+    fn test_ghostty_theme_update_appends_when_missing() {
+        let config = "# Fonts\nfont-size = 17";
+        let updated = GhosttyAdapter::update_theme_in_config(config, "tokyonight");
+        assert_eq!(updated, "# Fonts\nfont-size = 17\ntheme = tokyonight\n");
+
+        assert_eq!(
+            GhosttyAdapter::update_theme_in_config("", "tokyonight"),
+            "theme = tokyonight\n"
+        );
+    }
+
+    #[test]
+    fn test_ghostty_last_theme_line_wins() {
+        let config = "theme = first\nfont-size = 17\ntheme = second\n";
+        assert_eq!(
+            GhosttyAdapter::extract_theme_from_config(config),
+            Some("second".to_string())
+        );
+
+        let updated = GhosttyAdapter::update_theme_in_config(config, "third");
+        assert_eq!(updated, "theme = first\nfont-size = 17\ntheme = third\n");
+    }
+
+    #[test]
+    fn test_theme_pair_parsing() {
+        let pair = ThemePair::parse("dark:tokyonight, light:catppuccin-latte").unwrap();
+        assert_eq!(pair.light, "catppuccin-latte");
+        assert_eq!(pair.dark, "tokyonight");
+        assert_eq!(pair.to_string(), "light:catppuccin-latte,dark:tokyonight");
+
+        assert_eq!(ThemePair::parse("tokyonight"), None);
+        assert_eq!(ThemePair::parse("light:catppuccin-latte"), None);
+        assert_eq!(ThemePair::parse("light:,dark:tokyonight"), None);
+    }
+
+    #[tokio::test]
+    async fn test_ghostty_follows_config_file_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("config");
+        let theme_file = dir.path().join("theme.conf");
+        std::fs::write(
+            &main,
+            "theme = ignored\nconfig-file = theme.conf\nconfig-file = ?missing.conf\n",
+        )
+        .unwrap();
+        std::fs::write(&theme_file, "# Colors\ntheme = tokyonight\n").unwrap();
+
+        let adapter = GhosttyAdapter::with_path(main.clone());
+        assert_eq!(adapter.get_theme().await.unwrap(), "tokyonight");
+        assert_eq!(
+            adapter.config_files(),
+            vec![main.clone(), theme_file.clone()]
+        );
+
+        // The include holds the effective theme line, so that is the file edited
+        adapter
+            .set_theme("dark:tokyonight,light:catppuccin-latte")
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&theme_file).unwrap(),
+            "# Colors\ntheme = light:catppuccin-latte,dark:tokyonight\n"
+        );
+        assert!(std::fs::read_to_string(&main)
+            .unwrap()
+            .starts_with("theme = ignored\n"));
+        assert_eq!(
+            adapter.get_theme().await.unwrap(),
+            "light:catppuccin-latte,dark:tokyonight"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_replaces_symlink_target() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dotfiles = dir.path().join("dotfiles");
+        std::fs::create_dir(&dotfiles).unwrap();
+        let target = dotfiles.join("config");
+        std::fs::write(&target, "theme = old\n").unwrap();
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        // Relative link, as created by GNU stow
+        let link = dir.path().join("config");
+        std::os::unix::fs::symlink("dotfiles/config", &link).unwrap();
+
+        write_atomic(&link, "theme = new\n").unwrap();
+
+        assert!(std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "theme = new\n");
+        let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // No temporary files are left next to the target
+        assert_eq!(std::fs::read_dir(&dotfiles).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_creates_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("config.yaml");
+
+        write_atomic(&path, "settings: {}\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "settings: {}\n");
+    }
+
+    #[test]
+    fn test_alacritty_import_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        let adapter = AlacrittyAdapter::with_path(dir.path().join("alacritty.toml"))
+            .with_themes_dir(themes.clone());
+
+        let config = format!(
+            "# Alacritty\n[general]\nimport = [\n    \"{}/keys.toml\",\n    \"{}/tokyo_night.toml\", # theme\n]\nlive_config_reload = true\n\n[font]\nsize = 13\n",
+            dir.path().display(),
+            themes.display()
+        );
+        let updated = adapter
+            .update_theme_in_config(&config, &themes.join("github_light.toml"))
+            .unwrap();
+
+        assert_eq!(updated, config.replace("tokyo_night", "github_light"));
+        assert_eq!(
+            adapter.extract_theme_from_config(&updated).unwrap(),
+            Some("github_light".to_string())
+        );
+    }
+
+    #[test]
+    fn test_alacritty_import_legacy_and_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        let adapter = AlacrittyAdapter::with_path(dir.path().join("alacritty.toml"))
+            .with_themes_dir(themes.clone());
+        let theme_file = themes.join("tokyo_night.toml");
+
+        // Legacy top-level import is edited where it is
+        let legacy = "import = [\"/etc/alacritty/base.toml\"]\n\n[window]\nopacity = 0.9\n";
+        let updated = adapter.update_theme_in_config(legacy, &theme_file).unwrap();
+        assert!(updated.starts_with(&format!(
+            "import = [\"/etc/alacritty/base.toml\", \"{}\"]\n",
+            theme_file.display()
+        )));
+        assert!(!updated.contains("[general]"));
+
+        // Configs without imports get `general.import`
+        let updated = adapter
+            .update_theme_in_config("[window]\nopacity = 0.9\n", &theme_file)
+            .unwrap();
+        assert!(updated.contains(&format!(
+            "[general]\nimport = [\"{}\"]",
+            theme_file.display()
+        )));
+        assert_eq!(
+            adapter.extract_theme_from_config(&updated).unwrap(),
+            Some("tokyo_night".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alacritty_rejects_missing_theme_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let adapter = AlacrittyAdapter::with_path(dir.path().join("alacritty.toml"))
+            .with_themes_dir(dir.path().join("themes"));
+
+        assert!(adapter.set_theme("nope").await.is_err());
+        assert_eq!(adapter.get_theme().await.unwrap(), "default");
+    }
+
+    #[test]
+    fn test_kitty_theme_block() {
+        let config = "font_size 13.0\n\n# BEGIN_KITTY_THEME\n# Tokyo Night\ninclude current-theme.conf\n# END_KITTY_THEME\n\nmap ctrl+t new_tab\n";
+        let updated = KittyAdapter::update_theme_block(config, "Catppuccin-Latte");
+
+        assert_eq!(updated, config.replace("Tokyo Night", "Catppuccin-Latte"));
+        assert_eq!(
+            KittyAdapter::extract_theme_from_config(&updated),
+            Some("Catppuccin-Latte".to_string())
+        );
+
+        // Configs without a block get one appended
+        let updated = KittyAdapter::update_theme_block("font_size 13.0", "Tokyo Night");
+        assert_eq!(
+            updated,
+            "font_size 13.0\n\n# BEGIN_KITTY_THEME\n# Tokyo Night\ninclude current-theme.conf\n# END_KITTY_THEME\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kitty_remote_addresses() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("kitty-1234");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        // Regular files matching the pattern are not instances
+        std::fs::write(dir.path().join("kitty-5678"), "").unwrap();
+
+        let config = format!(
+            "allow_remote_control socket-only\nlisten_on unix:{}\n",
+            dir.path().join("kitty").display()
+        );
+        assert_eq!(
+            KittyAdapter::remote_addresses(&config),
+            vec![format!("unix:{}", socket.display())]
+        );
+
+        assert!(
+            KittyAdapter::remote_addresses(&format!("listen_on unix:{}\n", socket.display()))
+                .is_empty()
+        );
+        assert_eq!(
+            KittyAdapter::remote_addresses("allow_remote_control yes\nlisten_on unix:@kitty\n"),
+            vec!["unix:@kitty".to_string()]
+        );
+    }
+
+    #[derive(Default)]
+    struct RecordingRunner {
+        calls: std::sync::Mutex<Vec<Vec<String>>>,
+        stdout: String,
+        fail: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl CommandRunner for RecordingRunner {
+        async fn run(&self, program: &str, args: &[&str]) -> std::io::Result<CommandOutput> {
+            let mut call = vec![program.to_string()];
+            call.extend(args.iter().map(|arg| arg.to_string()));
+            self.calls.lock().unwrap().push(call);
+            Ok(CommandOutput {
+                success: !self.fail,
+                stdout: self.stdout.clone(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kitty_set_theme_updates_running_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        std::fs::create_dir(&themes).unwrap();
+        std::fs::write(themes.join("Tokyo Night.conf"), "background #1a1b26\n").unwrap();
+
+        let socket = dir.path().join("kitty-42");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let config_path = dir.path().join("kitty.conf");
+        std::fs::write(
+            &config_path,
+            format!(
+                "allow_remote_control yes\nlisten_on unix:{}\n",
+                dir.path().join("kitty").display()
+            ),
+        )
+        .unwrap();
+
+        let runner = std::sync::Arc::new(RecordingRunner::default());
+        let adapter = KittyAdapter::with_path(config_path)
+            .with_themes_dir(themes.clone())
+            .with_runner(runner.clone());
+
+        adapter.set_theme("Tokyo Night").await.unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("current-theme.conf")).unwrap(),
+            "background #1a1b26\n"
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "Tokyo Night");
+        let theme_file = themes.join("Tokyo Night.conf").display().to_string();
+        let address = format!("unix:{}", socket.display());
+        assert_eq!(
+            *runner.calls.lock().unwrap(),
+            vec![vec![
+                "kitty",
+                "@",
+                "--to",
+                address.as_str(),
+                "set-colors",
+                "--all",
+                "--configured",
+                theme_file.as_str(),
+            ]]
+        );
+
+        // IPC alone fails when no instance is listening
+        drop(_listener);
+        std::fs::remove_file(&socket).unwrap();
+        assert!(adapter
+            .set_theme_with("Tokyo Night", ApplyMethod::Ipc)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_wezterm_module_round_trip() {
+        let module = WezTermAdapter::render_module("Tokyo Night (Gogh) \"v2\"");
+        assert_eq!(
+            WezTermAdapter::extract_theme_from_module(&module),
+            Some("Tokyo Night (Gogh) \"v2\"".to_string())
+        );
+        assert_eq!(
+            WezTermAdapter::extract_theme_from_module("return 'Catppuccin Latte';\n"),
+            Some("Catppuccin Latte".to_string())
+        );
+        assert_eq!(
+            WezTermAdapter::extract_theme_from_module("return nil\n"),
+            None
+        );
+
+        assert!(WezTermAdapter::requires_module(
+            "local config = wezterm.config_builder()\nconfig.color_scheme = require 'themesync'\n"
+        ));
+        assert!(!WezTermAdapter::requires_module(
+            "-- config.color_scheme = require(\"themesync\")\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wezterm_set_theme_and_doctor() {
+        let dir = tempfile::tempdir().unwrap();
+        let adapter = WezTermAdapter::with_path(dir.path().join("themesync.lua"));
+
+        assert_eq!(adapter.get_theme().await.unwrap(), "default");
+        adapter.set_theme("Tokyo Night").await.unwrap();
+        assert_eq!(adapter.get_theme().await.unwrap(), "Tokyo Night");
+
+        let config_path = dir.path().join("wezterm.lua");
+        std::fs::write(&config_path, "return { font_size = 13 }\n").unwrap();
+        let problems = adapter.doctor().await;
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("require(\"themesync\")"));
+
+        std::fs::write(
+            &config_path,
+            "local config = {}\nconfig.color_scheme = require(\"themesync\")\nreturn config\n",
+        )
+        .unwrap();
+        assert!(adapter.doctor().await.is_empty());
+    }
+
+    #[test]
+    fn test_neovim_module_round_trip() {
+        let module = NeovimAdapter::render_module("dark:tokyonight");
+        assert!(
+            module.contains("vim.o.background = \"dark\"\nvim.cmd.colorscheme(\"tokyonight\")\n")
+        );
+        assert_eq!(
+            NeovimAdapter::extract_theme_from_module(&module),
+            Some("dark:tokyonight".to_string())
+        );
+
+        let module = NeovimAdapter::render_module("gruvbox");
+        assert!(!module.contains("background"));
+        assert_eq!(
+            NeovimAdapter::extract_theme_from_module(&module),
+            Some("gruvbox".to_string())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_neovim_discover_sockets() {
+        let dir = tempfile::tempdir().unwrap();
+        // Linux layout, plus the nested macOS layout
+        let linux = dir.path().join("nvim.100.0");
+        let nested = dir.path().join("nvim.user").join("abc123");
+        std::fs::create_dir_all(&nested).unwrap();
+        let macos = nested.join("nvim.200.0");
+        let _linux = std::os::unix::net::UnixListener::bind(&linux).unwrap();
+        let _macos = std::os::unix::net::UnixListener::bind(&macos).unwrap();
+        let _other = std::os::unix::net::UnixListener::bind(dir.path().join("tmux-0")).unwrap();
+
+        assert_eq!(
+            NeovimAdapter::discover_sockets(dir.path()),
+            vec![linux, macos]
+        );
+    }
+
+    /// Answers one msgpack-RPC request like Neovim, returning the request it received
+    #[cfg(unix)]
+    async fn fake_nvim_server(
+        listener: tokio::net::UnixListener,
+        error: Option<&'static str>,
+    ) -> rmpv::Value {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut received = Vec::new();
+        let request = loop {
+            let mut chunk = [0u8; 1024];
+            let read = stream.read(&mut chunk).await.unwrap();
+            received.extend_from_slice(&chunk[..read]);
+            if let Ok(request) = rmpv::decode::read_value(&mut received.as_slice()) {
+                break request;
+            }
+        };
+
+        let error = match error {
+            Some(message) => rmpv::Value::Array(vec![0.into(), message.into()]),
+            None => rmpv::Value::Nil,
+        };
+        // A notification first, as Neovim may interleave them with responses
+        let messages = [
+            rmpv::Value::Array(vec![2.into(), "redraw".into(), rmpv::Value::Array(vec![])]),
+            rmpv::Value::Array(vec![1.into(), request[1].clone(), error, rmpv::Value::Nil]),
+        ];
+        let mut encoded = Vec::new();
+        for message in &messages {
+            rmpv::encode::write_value(&mut encoded, message).unwrap();
+        }
+        stream.write_all(&encoded).await.unwrap();
+        request
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_neovim_set_theme_switches_running_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let runtime_dir = dir.path().join("run");
+        std::fs::create_dir(&runtime_dir).unwrap();
+        let listener = tokio::net::UnixListener::bind(runtime_dir.join("nvim.42.0")).unwrap();
+        let server = tokio::spawn(fake_nvim_server(listener, None));
+
+        let module_path = dir.path().join("nvim").join("lua").join("themesync.lua");
+        let adapter = NeovimAdapter::with_path(module_path).with_runtime_dir(runtime_dir.clone());
+        adapter.set_theme("light:tokyonight-day").await.unwrap();
+
+        let request = server.await.unwrap();
+        assert_eq!(request[2].as_str(), Some("nvim_command"));
+        assert_eq!(
+            request[3][0].as_str(),
+            Some("set background=light | colorscheme tokyonight-day")
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "light:tokyonight-day");
+
+        // Errors from the instance fail an IPC-only switch
+        let listener = tokio::net::UnixListener::bind(runtime_dir.join("nvim.43.0")).unwrap();
+        std::fs::remove_file(runtime_dir.join("nvim.42.0")).unwrap();
+        let server = tokio::spawn(fake_nvim_server(
+            listener,
+            Some("E185: Cannot find color scheme 'nope'"),
+        ));
+        let error = adapter
+            .set_theme_with("nope", ApplyMethod::Ipc)
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("E185"));
+        server.await.unwrap();
+
+        // Names that would chain another Ex command never reach the instance
+        let error = adapter
+            .set_theme_with("dark:foo | !touch pwned", ApplyMethod::Ipc)
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("not a valid Neovim colorscheme"));
+        assert_eq!(adapter.get_theme().await.unwrap(), "light:tokyonight-day");
+    }
+
+    #[test]
+    fn test_zed_theme_shapes() {
+        // Plain string setting, with comments kept
+        let settings =
+            "// Zed settings\n{\n  \"theme\": \"One Dark\", // mine\n  \"ui_font_size\": 16\n}\n";
+        let updated = ZedAdapter::update_theme(settings, "One Light", None).unwrap();
+        assert_eq!(updated, settings.replace("One Dark", "One Light"));
+
+        // Object setting: only the active variant changes
+        let settings = "{\n  \"theme\": {\n    \"mode\": \"system\",\n    \"light\": \"One Light\",\n    \"dark\": \"One Dark\"\n  }\n}\n";
+        let updated =
+            ZedAdapter::update_theme(settings, "Ayu Dark", Some(Appearance::Dark)).unwrap();
+        assert_eq!(updated, settings.replace("One Dark", "Ayu Dark"));
+        let updated =
+            ZedAdapter::update_theme(settings, "Ayu Light", Some(Appearance::Light)).unwrap();
+        assert_eq!(updated, settings.replace("One Light", "Ayu Light"));
+
+        // A pair turns a string setting into a system-mode object
+        let updated = ZedAdapter::update_theme(
+            "{\n  \"theme\": \"One Dark\"\n}\n",
+            "light:One Light,dark:One Dark",
+            None,
+        )
+        .unwrap();
+        assert_eq!(updated, settings);
+    }
+
+    #[tokio::test]
+    async fn test_zed_effective_theme_follows_system() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings_path = dir.path().join("settings.json");
+        std::fs::write(
+            &settings_path,
+            "{ \"theme\": { \"mode\": \"system\", \"light\": \"One Light\", \"dark\": \"One Dark\" } }",
+        )
+        .unwrap();
+
+        let stdout = if cfg!(target_os = "macos") {
+            "Dark\n"
+        } else {
+            "'prefer-dark'\n"
+        };
+        let runner = std::sync::Arc::new(RecordingRunner {
+            stdout: stdout.to_string(),
+            ..Default::default()
+        });
+        let adapter = ZedAdapter::with_path(settings_path.clone()).with_runner(runner.clone());
+        assert_eq!(adapter.get_theme().await.unwrap(), "One Dark");
+        assert_eq!(runner.calls.lock().unwrap().len(), 1);
+
+        // A fixed mode needs no detection
+        std::fs::write(
+            &settings_path,
+            "{ \"theme\": { \"mode\": \"light\", \"light\": \"One Light\", \"dark\": \"One Dark\" } }",
+        )
+        .unwrap();
+        assert_eq!(adapter.get_theme().await.unwrap(), "One Light");
+        assert_eq!(runner.calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_vscode_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_dir = dir.path().join("User");
+        for id in ["-1a2b3c", "-4d5e6f", "-7a8b9c", "-aa11", "-bb22", "-cc33"] {
+            std::fs::create_dir_all(user_dir.join("profiles").join(id)).unwrap();
+        }
+        std::fs::create_dir_all(user_dir.join("globalStorage")).unwrap();
+        std::fs::write(
+            user_dir.join("globalStorage/storage.json"),
+            r#"{"userDataProfiles": [
+                {"location": "-1a2b3c", "name": "Work Stuff"},
+                {"location": "-4d5e6f", "name": "Shared", "useDefaultFlags": {"settings": true}},
+                {"location": "-aa11", "name": "Work"},
+                {"location": "-bb22", "name": "work!"},
+                {"location": "-cc33", "name": "!!!"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let adapter = VsCodeAdapter::for_variant(VsCodeVariant::VsCodium)
+            .with_settings_path(user_dir.join("settings.json"));
+        let profiles = adapter.profiles();
+
+        let keys: Vec<&str> = profiles.iter().map(|p| p.config_key()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "vscodium-profile-work-stuff",
+                "vscodium-profile-7a8b9c",
+                "vscodium-profile-work-aa11",
+                "vscodium-profile-work-bb22",
+                "vscodium-profile-cc33",
+            ]
+        );
+        assert_eq!(profiles[0].app_name(), "VSCodium (Work Stuff)");
+
+        profiles[0].set_theme("Solarized Light").await.unwrap();
+        let written =
+            std::fs::read_to_string(user_dir.join("profiles/-1a2b3c/settings.json")).unwrap();
+        assert!(written.contains("\"workbench.colorTheme\": \"Solarized Light\""));
+
+        // Errors name the profile, not plain VS Code
+        std::fs::write(user_dir.join("profiles/-aa11/settings.json"), "[]").unwrap();
+        let error = profiles[2].set_theme("Dracula").await.unwrap_err();
+        assert_eq!(error.app_name, "vscodium-profile-work-aa11");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_vscode_availability() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let user_dir = dir.path().join("User");
+        for id in ["-1a2b3c", "-4d5e6f"] {
+            std::fs::create_dir_all(user_dir.join("profiles").join(id)).unwrap();
+        }
+        std::fs::write(user_dir.join("profiles/-1a2b3c/settings.json"), "{}").unwrap();
+
+        // A stub CLI standing in for an install outside the usual prefixes
+        let bin = dir.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let log = dir.path().join("cursor.log");
+        let stub = bin.join("cursor");
+        std::fs::write(
+            &stub,
+            format!(
+                "#!/bin/sh
+echo \"$@\" >> '{}'\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        // An explicit path counts as installed even before settings.json exists
+        let adapter = VsCodeAdapter::for_variant(VsCodeVariant::Cursor)
+            .with_settings_path(user_dir.join("settings.json"))
+            .with_runner(std::sync::Arc::new(SystemRunner::with_search_path(&bin)));
+        assert!(adapter.is_available().await);
+
+        // Profiles are found through their settings.json, or else through the CLI on PATH
+        let profiles = adapter.profiles();
+        assert!(profiles[0].is_available().await);
+        assert!(!log.exists());
+        assert!(profiles[1].is_available().await);
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "--version\n");
+    }
+
+    #[test]
+    fn test_vscode_preferred_themes() {
+        let settings = "{\n    // Editor\n    \"editor.fontSize\": 14,\n    \"workbench.colorTheme\": \"Dracula\"\n}\n";
+
+        let updated =
+            VsCodeAdapter::update_theme(settings, "light:GitHub Light,dark:Dracula", None).unwrap();
+        assert!(updated.starts_with("{\n    // Editor\n"));
+        let parsed = match jsonc::parse(&updated).unwrap() {
+            serde_json::Value::Object(map) => map,
+            _ => unreachable!(),
+        };
+        assert_eq!(parsed["workbench.preferredLightColorTheme"], "GitHub Light");
+        assert_eq!(parsed["workbench.preferredDarkColorTheme"], "Dracula");
+        assert_eq!(parsed["window.autoDetectColorScheme"], true);
+
+        assert_eq!(
+            VsCodeAdapter::effective_theme(&parsed, Some(Appearance::Light)),
+            "GitHub Light"
+        );
+        assert_eq!(
+            VsCodeAdapter::effective_theme(&parsed, Some(Appearance::Dark)),
+            "Dracula"
+        );
+
+        // With auto-detect on, a plain name replaces the preferred theme in effect
+        let updated =
+            VsCodeAdapter::update_theme(&updated, "Solarized Light", Some(Appearance::Light))
+                .unwrap();
+        assert!(updated.contains("\"workbench.preferredLightColorTheme\": \"Solarized Light\""));
+        assert!(updated.contains("\"workbench.colorTheme\": \"Dracula\""));
+    }
+
+    #[tokio::test]
+    async fn test_tmux_sources_theme_into_running_server() {
+        use std::process::Command;
+
+        if Command::new("tmux").arg("-V").output().is_err() {
+            return; // tmux not installed
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        std::fs::create_dir(&themes).unwrap();
+        std::fs::write(
+            themes.join("tokyonight.conf"),
+            "set -g status-style 'bg=#1a1b26,fg=#a9b1d6'\nset -g pane-border-style 'fg=#3b4261'",
+        )
+        .unwrap();
+
+        // A private server (-L) whose socket lives under our temp dir
+        let tmux_tmpdir = dir.path().join("sockets");
+        std::fs::create_dir(&tmux_tmpdir).unwrap();
+        let started = Command::new("tmux")
+            .args([
+                "-L",
+                "themesync-test",
+                "-f",
+                "/dev/null",
+                "new-session",
+                "-d",
+            ])
+            .env("TMUX_TMPDIR", &tmux_tmpdir)
+            .status()
+            .unwrap();
+        assert!(started.success());
+        let socket_dir = std::fs::read_dir(&tmux_tmpdir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let socket = socket_dir.join("themesync-test");
+
+        let adapter = TmuxAdapter::with_path(dir.path().join("themesync.conf"))
+            .with_themes_dir(themes)
+            .with_socket_dir(socket_dir);
+        let result = adapter.set_theme_with("tokyonight", ApplyMethod::Ipc).await;
+
+        let style = Command::new("tmux")
+            .arg("-S")
+            .arg(&socket)
+            .args(["show-options", "-gv", "status-style"])
+            .output()
+            .unwrap();
+        let _ = Command::new("tmux")
+            .arg("-S")
+            .arg(&socket)
+            .arg("kill-server")
+            .status();
+
+        result.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&style.stdout).trim(),
+            "bg=#1a1b26,fg=#a9b1d6"
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "tokyonight");
+    }
+
+    #[tokio::test]
+    async fn test_tmux_without_running_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        std::fs::create_dir(&themes).unwrap();
+        std::fs::write(
+            themes.join("catppuccin-latte.conf"),
+            "set -g status-style bg=white\n",
+        )
+        .unwrap();
+
+        let runner = std::sync::Arc::new(RecordingRunner::default());
+        let adapter = TmuxAdapter::with_path(dir.path().join("themesync.conf"))
+            .with_themes_dir(themes)
+            .with_socket_dir(dir.path().join("no-sockets"))
+            .with_runner(runner.clone());
+
+        adapter.set_theme("catppuccin-latte").await.unwrap();
+        assert!(runner.calls.lock().unwrap().is_empty());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("themesync.conf")).unwrap(),
+            TmuxAdapter::render_managed_file("catppuccin-latte", "set -g status-style bg=white\n")
+        );
+        assert!(adapter
+            .set_theme_with("catppuccin-latte", ApplyMethod::Ipc)
+            .await
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_tmux_builtin_themes_and_unreachable_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let sockets = dir.path().join("sockets");
+        std::fs::create_dir(&sockets).unwrap();
+        let _listener = std::os::unix::net::UnixListener::bind(sockets.join("default")).unwrap();
+
+        let runner = std::sync::Arc::new(RecordingRunner {
+            fail: true,
+            ..Default::default()
+        });
+        let adapter = TmuxAdapter::with_path(dir.path().join("themesync.conf"))
+            .with_themes_dir(dir.path().join("themes"))
+            .with_socket_dir(sockets)
+            .with_runner(runner.clone());
+
+        // The default themes need no snippet files, and Auto shrugs off the server error
+        for theme in adapter.default_themes().values() {
+            adapter.set_theme(theme).await.unwrap();
+            assert_eq!(adapter.get_theme().await.unwrap(), *theme);
+        }
+        assert_eq!(runner.calls.lock().unwrap().len(), 2);
+        assert!(adapter
+            .set_theme_with("tokyonight", ApplyMethod::Ipc)
+            .await
+            .is_err());
+        assert!(adapter.set_theme("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_emacs_switches_running_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme_file = dir.path().join("themesync-theme.el");
+        let runner = std::sync::Arc::new(RecordingRunner {
+            stdout: "(modus-operandi)\n".to_string(),
+            ..Default::default()
+        });
+        let adapter = EmacsAdapter::with_path(theme_file.clone()).with_runner(runner.clone());
+
+        adapter.set_theme("modus-operandi").await.unwrap();
+        assert_eq!(
+            runner.calls.lock().unwrap()[0],
+            vec![
+                "emacsclient",
+                "--eval",
+                "(progn (mapc #'disable-theme custom-enabled-themes) (load-theme 'modus-operandi t))",
+            ]
+        );
+        let contents = std::fs::read_to_string(&theme_file).unwrap();
+        assert!(contents.contains("(load-theme 'modus-operandi t)\n"));
+
+        // The live server answers get_theme
+        assert_eq!(adapter.get_theme().await.unwrap(), "modus-operandi");
+
+        // Names that would inject Elisp are rejected
+        assert!(adapter.set_theme("x) (shell-command \"rm\"").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_emacs_without_server_uses_generated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = std::sync::Arc::new(RecordingRunner {
+            fail: true,
+            ..Default::default()
+        });
+        let adapter = EmacsAdapter::with_path(dir.path().join("themesync-theme.el"))
+            .with_runner(runner.clone());
+
+        assert_eq!(adapter.get_theme().await.unwrap(), "default");
+        // No server is not an error unless IPC was asked for explicitly
+        adapter.set_theme("modus-vivendi").await.unwrap();
+        assert_eq!(adapter.get_theme().await.unwrap(), "modus-vivendi");
+        assert!(adapter
+            .set_theme_with("modus-vivendi", ApplyMethod::Ipc)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_sublime_sets_scheme_and_theme() {
+        let adapter =
+            SublimeAdapter::with_path(SublimeProduct::Text, "Preferences.sublime-settings".into());
+        let settings = "// Settings in here override those in \"Default/Preferences.sublime-settings\"\n{\n\t\"font_size\": 12,\n\t\"color_scheme\": \"Mariana.sublime-color-scheme\",\n\t\"theme\": \"Default Dark.sublime-theme\",\n}\n";
+
+        let updated = adapter
+            .update_theme(
+                settings,
+                "Breakers.sublime-color-scheme|Default.sublime-theme",
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            updated,
+            settings
+                .replace("Mariana", "Breakers")
+                .replace("Default Dark.", "Default.")
+        );
+
+        // A pair switches both settings to auto with light/dark variants
+        let updated = adapter
+            .update_theme(
+                settings,
+                "light:Breakers.sublime-color-scheme|Default.sublime-theme,dark:Mariana.sublime-color-scheme|Default Dark.sublime-theme",
+                None,
+            )
+            .unwrap();
+        let parsed = match jsonc::parse(&updated).unwrap() {
+            serde_json::Value::Object(map) => map,
+            _ => unreachable!(),
+        };
+        assert_eq!(parsed["color_scheme"], "auto");
+        assert_eq!(parsed["theme"], "auto");
+        assert_eq!(
+            parsed["light_color_scheme"],
+            "Breakers.sublime-color-scheme"
+        );
+        assert_eq!(parsed["dark_theme"], "Default Dark.sublime-theme");
+        assert_eq!(
+            adapter.effective_theme(&parsed, Some(Appearance::Light)),
+            "Breakers.sublime-color-scheme|Default.sublime-theme"
+        );
+
+        // With auto on, a plain value edits the variant in effect
+        let updated = adapter
+            .update_theme(
+                &updated,
+                "Celeste.sublime-color-scheme",
+                Some(Appearance::Light),
+            )
+            .unwrap();
+        assert!(updated.contains("\"light_color_scheme\": \"Celeste.sublime-color-scheme\""));
+        assert!(updated.contains("\"dark_color_scheme\": \"Mariana.sublime-color-scheme\""));
+    }
+
+    #[tokio::test]
+    async fn test_sublime_merge_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings_path = dir.path().join("Preferences.sublime-settings");
+        let runner = std::sync::Arc::new(RecordingRunner {
+            fail: true,
+            ..Default::default()
+        });
+        let adapter = SublimeAdapter::with_path(SublimeProduct::Merge, settings_path.clone())
+            .with_runner(runner);
+
+        assert_eq!(
+            adapter.default_themes()["light"],
+            "Merge Light.sublime-theme"
+        );
+        // Unknown appearance falls back to the dark variant of the default `auto` theme
+        assert_eq!(
+            adapter.get_theme().await.unwrap(),
+            "Merge Dark.sublime-theme"
+        );
+
+        std::fs::write(&settings_path, "{\"theme\": \"Merge Dark.sublime-theme\"}").unwrap();
+        adapter
+            .set_theme("Merge Light.sublime-theme")
+            .await
+            .unwrap();
+        assert_eq!(
+            adapter.get_theme().await.unwrap(),
+            "Merge Light.sublime-theme"
+        );
+    }
+
+    #[test]
+    fn test_xml_set_attribute_preserves_document() {
+        let path = [
+            xml::Selector::new("application"),
+            xml::Selector::new("component").with_attribute("name", "LafManager"),
+            xml::Selector::new("laf"),
+        ];
+        let laf = "<application>\n  <!-- <laf themeId=\"Old\" /> -->\n  <component name=\"LafManager\">\n    <laf class-name=\"X\" themeId=\"Darcula\" />\n  </component>\n</application>\n";
+
+        let updated = xml::set_attribute(laf, &path, "themeId", "Light & Bright");
+        assert_eq!(
+            updated,
+            laf.replace("\"Darcula\"", "\"Light &amp; Bright\"")
+        );
+        assert_eq!(
+            xml::get_attribute(&updated, &path, "themeId").unwrap(),
+            "Light & Bright"
+        );
+
+        // Missing elements are created under their parent
+        let updated = xml::set_attribute(
+            "<application>\n</application>\n",
+            &path,
+            "themeId",
+            "Darcula",
+        );
+        assert_eq!(
+            updated,
+            "<application>\n  <component name=\"LafManager\">\n    <laf themeId=\"Darcula\" />\n  </component>\n</application>\n"
+        );
+        assert_eq!(
+            xml::set_attribute("", &path[..1], "version", "1"),
+            "<application version=\"1\" />\n"
+        );
+        // Markup inside CDATA is text, not tags
+        let laf = "<application>\n  <component name=\"LafManager\">\n    <note><![CDATA[ a > b <laf themeId=\"Fake\"> ]]></note>\n    <laf themeId=\"Darcula\" />\n  </component>\n</application>\n";
+        let updated = xml::set_attribute(laf, &path, "themeId", "Light");
+        assert_eq!(updated, laf.replace("\"Darcula\"", "\"Light\""));
+        assert_eq!(
+            xml::get_attribute(laf, &path, "themeId").unwrap(),
+            "Darcula"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jetbrains_discovers_families_and_sets_theme() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in [
+            "RustRover2024.1",
+            "RustRover2024.2",
+            "IdeaIC2023.3",
+            "consentOptions",
+        ] {
+            std::fs::create_dir_all(root.path().join(dir).join("options")).unwrap();
+        }
+        let laf_path = root.path().join("RustRover2024.2/options/laf.xml");
+        std::fs::write(
+            &laf_path,
+            "<application>\n  <component name=\"LafManager\" autodetect=\"true\">\n    <laf themeId=\"ExperimentalDark\" />\n  </component>\n</application>\n",
+        )
+        .unwrap();
+
+        let adapters = JetBrainsAdapter::discover(root.path());
+        let keys: Vec<&str> = adapters.iter().map(|a| a.config_key()).collect();
+        assert_eq!(keys, ["jetbrains-ideaic", "jetbrains-rustrover"]);
+        assert_eq!(adapters[0].app_name(), "IntelliJ IDEA Community");
+
+        let rustrover = &adapters[1];
+        assert!(rustrover.needs_restart());
+        assert_eq!(
+            rustrover.get_theme().await.unwrap(),
+            "ExperimentalDark|Dark"
+        );
+
+        rustrover
+            .set_theme("ExperimentalLight|Light")
+            .await
+            .unwrap();
+        assert_eq!(
+            rustrover.get_theme().await.unwrap(),
+            "ExperimentalLight|Light"
+        );
+        let laf = std::fs::read_to_string(&laf_path).unwrap();
+        assert!(laf.contains("<component name=\"LafManager\" autodetect=\"false\">"));
+
+        // Every installed version is updated
+        let old_scheme = std::fs::read_to_string(
+            root.path()
+                .join("RustRover2024.1/options/colors.scheme.xml"),
+        )
+        .unwrap();
+        assert!(old_scheme.contains("<global_color_scheme name=\"Light\" />"));
+    }
+
+    #[test]
+    fn test_ini_set_preserves_document() {
+        let settings = "# GTK settings\n[Settings]\ngtk-theme-name = Adwaita\ngtk-font-name=Cantarell 11\n\n[Other]\nkey=value\n";
+
+        let updated = ini::set(settings, "Settings", "gtk-theme-name", "Yaru-dark");
+        assert_eq!(updated, settings.replace("= Adwaita", "= Yaru-dark"));
+        assert_eq!(
+            ini::get(&updated, "Settings", "gtk-theme-name").unwrap(),
+            "Yaru-dark"
+        );
+
+        // Missing keys go at the end of their section, missing sections at the end
+        let updated = ini::set(settings, "Settings", "gtk-icon-theme-name", "Papirus");
+        assert!(updated.contains("Cantarell 11\ngtk-icon-theme-name=Papirus\n\n[Other]"));
+        let updated = ini::set(settings, "New", "a", "b");
+        assert!(updated.ends_with("key=value\n\n[New]\na=b\n"));
+        assert_eq!(ini::set("", "Settings", "a", "b"), "[Settings]\na=b\n");
+
+        assert_eq!(
+            ini::clear_section(settings, "Settings"),
+            "# GTK settings\n[Settings]\n\n[Other]\nkey=value\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_gtk_sets_settings_ini_and_gsettings() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = tempfile::tempdir().unwrap();
+        let config_dir = home.path().join(".config");
+        std::fs::create_dir_all(config_dir.join("gtk-3.0")).unwrap();
+        std::fs::write(
+            config_dir.join("gtk-3.0/settings.ini"),
+            "[Settings]\ngtk-theme-name=Adwaita\ngtk-cursor-theme-name=Bibata\n",
+        )
+        .unwrap();
+
+        // A stub gsettings that logs its arguments
+        let bin = home.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let log = home.path().join("gsettings.log");
+        let stub = bin.join("gsettings");
+        std::fs::write(
+            &stub,
+            format!("#!/bin/sh\necho \"$@\" >> '{}'\n", log.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let adapter = GtkAdapter::with_path(config_dir.clone())
+            .with_runner(std::sync::Arc::new(SystemRunner::with_search_path(&bin)));
+        adapter
+            .set_theme("Adwaita-dark|Papirus-Dark")
+            .await
+            .unwrap();
+
+        let gtk3 = std::fs::read_to_string(config_dir.join("gtk-3.0/settings.ini")).unwrap();
+        assert_eq!(
+            gtk3,
+            "[Settings]\ngtk-theme-name=Adwaita-dark\ngtk-cursor-theme-name=Bibata\ngtk-application-prefer-dark-theme=true\ngtk-icon-theme-name=Papirus-Dark\n"
+        );
+        let gtk4 = std::fs::read_to_string(config_dir.join("gtk-4.0/settings.ini")).unwrap();
+        assert!(gtk4.starts_with("[Settings]\ngtk-theme-name=Adwaita-dark\n"));
+        assert_eq!(
+            adapter.get_theme().await.unwrap(),
+            "dark:Adwaita-dark|Papirus-Dark"
+        );
+
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "set org.gnome.desktop.interface color-scheme 'prefer-dark'\n\
+             set org.gnome.desktop.interface gtk-theme 'Adwaita-dark'\n\
+             set org.gnome.desktop.interface icon-theme 'Papirus-Dark'\n"
+        );
+
+        // Without gsettings on PATH, the files are still written
+        let adapter = GtkAdapter::with_path(config_dir.clone()).with_runner(std::sync::Arc::new(
+            SystemRunner::with_search_path(home.path()),
+        ));
+        adapter.set_theme("light:Adwaita").await.unwrap();
+        assert_eq!(
+            adapter.get_theme().await.unwrap(),
+            "light:Adwaita|Papirus-Dark"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gtk_rollback_restores_gsettings() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = dir.path().join("gtk-3.0/settings.ini");
+        std::fs::create_dir_all(settings.parent().unwrap()).unwrap();
+        std::fs::write(&settings, "[Settings]\ngtk-theme-name=Adwaita\n").unwrap();
+
+        // Every `gsettings get` reports the value GNOME had before the switch
+        let runner = std::sync::Arc::new(RecordingRunner {
+            stdout: "'Adwaita'\n".to_string(),
+            ..Default::default()
+        });
+        let adapter = GtkAdapter::with_path(dir.path().to_path_buf()).with_runner(runner.clone());
+        let snapshot = AdapterSnapshot::capture(&adapter).await.unwrap();
+        adapter.set_theme("dark:Yaru-dark|Papirus").await.unwrap();
+
+        runner.calls.lock().unwrap().clear();
+        snapshot.restore(&adapter).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&settings).unwrap(),
+            "[Settings]\ngtk-theme-name=Adwaita\n"
+        );
+        let calls: Vec<String> = runner
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|call| call.join(" "))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                "gsettings set org.gnome.desktop.interface color-scheme 'Adwaita'",
+                "gsettings set org.gnome.desktop.interface gtk-theme 'Adwaita'",
+                "gsettings set org.gnome.desktop.interface icon-theme 'Adwaita'",
+            ]
+        );
+
+        // Without gsettings there is nothing beyond settings.ini to snapshot
+        let runner = std::sync::Arc::new(RecordingRunner {
+            fail: true,
+            ..Default::default()
+        });
+        let adapter = GtkAdapter::with_path(dir.path().to_path_buf()).with_runner(runner);
+        assert_eq!(adapter.capture_state().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_kde_copies_color_scheme_into_kdeglobals() {
+        let dir = tempfile::tempdir().unwrap();
+        let schemes = dir.path().join("color-schemes");
+        std::fs::create_dir(&schemes).unwrap();
+        std::fs::write(
+            schemes.join("Nordic.colors"),
+            "[Colors:Window]\nBackgroundNormal=46,52,64\nForegroundNormal=216,222,233\n\n[General]\nColorScheme=Nordic\nName=Nordic\n\n[WM]\nactiveBackground=46,52,64\n",
+        )
+        .unwrap();
+        let kdeglobals = dir.path().join("kdeglobals");
+        let original = "[General]\nColorScheme=BreezeLight\nfixed=Hack,10\n\n[Colors:Window]\nBackgroundNormal=239,240,241\nDecorationFocus=61,174,233\n\n[KDE]\nSingleClick=false\n";
+        std::fs::write(&kdeglobals, original).unwrap();
+
+        // Without plasma-apply-colorscheme, auto falls back to editing kdeglobals; colors
+        // the old scheme set in a group the new one defines are dropped
+        let runner = std::sync::Arc::new(RecordingRunner {
+            fail: true,
+            ..Default::default()
+        });
+        let adapter = KdeAdapter::with_path(kdeglobals.clone())
+            .with_themes_dir(schemes)
+            .with_runner(runner.clone());
+        let snapshot = AdapterSnapshot::capture(&adapter).await.unwrap();
+        adapter.set_theme("Nordic").await.unwrap();
+
+        assert_eq!(
+            runner.calls.lock().unwrap().as_slice(),
+            [["plasma-apply-colorscheme", "Nordic"]]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&kdeglobals).unwrap(),
+            "[General]\nColorScheme=Nordic\nfixed=Hack,10\n\n[Colors:Window]\nBackgroundNormal=46,52,64\nForegroundNormal=216,222,233\n\n[KDE]\nSingleClick=false\n\n[WM]\nactiveBackground=46,52,64\n"
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "Nordic");
+
+        let missing = adapter.set_theme_with("Missing", ApplyMethod::File).await;
+        assert!(missing
+            .unwrap_err()
+            .message
+            .contains("Missing.colors not found"));
+
+        // A rollback reapplies the old scheme for running apps and restores kdeglobals
+        snapshot.restore(&adapter).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&kdeglobals).unwrap(), original);
+        assert_eq!(
+            runner.calls.lock().unwrap().last().unwrap(),
+            &["plasma-apply-colorscheme", "BreezeLight"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_windows_terminal_sets_default_color_scheme() {
+        let root = tempfile::tempdir().unwrap();
+        let local_state = root.path().join(
+            "Users/alex/AppData/Local/Packages/Microsoft.WindowsTerminal_8wekyb3d8bbwe/LocalState",
+        );
+        std::fs::create_dir_all(&local_state).unwrap();
+        std::fs::create_dir_all(root.path().join("Users/Public/AppData/Local/Packages")).unwrap();
+        let settings_path = local_state.join("settings.json");
+        let settings = "{\n    // Startup profile\n    \"defaultProfile\": \"{61c54bbd}\",\n    \"profiles\": {\n        \"defaults\": {},\n        \"list\": []\n    }\n}\n";
+        std::fs::write(&settings_path, settings).unwrap();
+
+        // Preview and unpackaged installs are found too
+        let preview = root.path().join(
+            "Users/alex/AppData/Local/Packages/Microsoft.WindowsTerminalPreview_8wekyb3d8bbwe/LocalState/settings.json",
+        );
+        let unpackaged = root
+            .path()
+            .join("Users/sam/AppData/Local/Microsoft/Windows Terminal/settings.json");
+        for path in [&preview, &unpackaged] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "{}").unwrap();
+        }
+        assert_eq!(
+            WindowsTerminalAdapter::discover_settings(root.path()),
+            vec![preview.clone(), settings_path.clone(), unpackaged]
+        );
+
+        std::fs::remove_file(&preview).unwrap();
+        std::fs::remove_dir_all(root.path().join("Users/sam")).unwrap();
+        let adapter = WindowsTerminalAdapter::with_root(root.path().to_path_buf());
+        assert_eq!(adapter.get_theme().await.unwrap(), "Campbell");
+
+        adapter.set_theme("One Half Dark|dark").await.unwrap();
+        let updated = std::fs::read_to_string(&settings_path).unwrap();
+        assert!(updated.starts_with("{\n    // Startup profile\n"));
+        assert!(updated.contains("\"list\": []"));
+        assert_eq!(adapter.get_theme().await.unwrap(), "One Half Dark|dark");
+
+        // A byte order mark is skipped when parsing and kept on write
+        let with_bom = format!("\u{feff}{}", settings);
+        let updated = WindowsTerminalAdapter::update_settings(&with_bom, "Campbell").unwrap();
+        assert!(updated.starts_with("\u{feff}{\n    // Startup profile\n"));
+        assert!(updated.contains("\"colorScheme\": \"Campbell\""));
+
+        // Legacy settings with a plain profile list have nowhere to put defaults
+        let legacy = WindowsTerminalAdapter::update_settings("{\"profiles\": []}", "Campbell");
+        assert!(legacy.is_err());
+
+        assert!(adapters::windows_terminal::is_wsl_kernel(
+            "Linux version 5.15.153.1-microsoft-standard-WSL2 (root@941d701f84f1)"
+        ));
+        assert!(!adapters::windows_terminal::is_wsl_kernel(
+            "Linux version 6.8.0-45-generic (buildd@lcy02-amd64-075)"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_foot_points_include_at_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        std::fs::create_dir(&themes).unwrap();
+        std::fs::write(themes.join("nord"), "[colors]\nbackground=2e3440\n").unwrap();
+        std::fs::write(
+            themes.join("solarized-light"),
+            "[colors]\nbackground=fdf6e3\n",
+        )
+        .unwrap();
+
+        let config_path = dir.path().join("foot.ini");
+        let config = format!(
+            "font=Iosevka:size=11\ninclude=~/.config/foot/keys.ini\ninclude={}\n\n[colors]\nalpha=0.95\n",
+            themes.join("nord").display()
+        );
+        std::fs::write(&config_path, &config).unwrap();
+
+        let adapter = FootAdapter::with_path(config_path.clone()).with_themes_dir(themes.clone());
+        assert_eq!(adapter.get_theme().await.unwrap(), "nord");
+        assert!(adapter.needs_restart());
+        assert!(adapter.doctor().await.is_empty());
+
+        adapter.set_theme("solarized-light").await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&config_path).unwrap(),
+            config.replace("/nord", "/solarized-light")
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "solarized-light");
+        assert!(adapter.set_theme("missing").await.is_err());
+
+        // A [main] section gets the include added when it has none
+        std::fs::write(
+            &config_path,
+            "[main]\nfont=Iosevka\n\n[colors]\nbackground=000000\n",
+        )
+        .unwrap();
+        adapter.set_theme("nord").await.unwrap();
+        let updated = std::fs::read_to_string(&config_path).unwrap();
+        assert!(updated.starts_with(&format!(
+            "[main]\nfont=Iosevka\ninclude={}\n\n",
+            themes.join("nord").display()
+        )));
+        assert_eq!(adapter.doctor().await.len(), 1);
+    }
+
+    #[test]
+    fn test_foot_matches_home_relative_include() {
+        let home = dirs::home_dir().unwrap();
+        let adapter = FootAdapter::with_path(std::path::PathBuf::from("foot.ini"))
+            .with_themes_dir(home.join(".local/share/foot/themes"));
+        let config = "include=~/.config/foot/keys.ini\ninclude=~/.local/share/foot/themes/nord\n";
+
+        assert_eq!(adapter.extract_theme(config).as_deref(), Some("nord"));
+
+        // The `~` line is replaced in place rather than getting a second include
+        let theme_path = home.join(".local/share/foot/themes/dracula");
+        assert_eq!(
+            adapter.update_include(config, &theme_path),
+            format!(
+                "include=~/.config/foot/keys.ini\ninclude={}\n",
+                theme_path.display()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_konsole_sets_color_scheme_in_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = dir.path().join("konsole");
+        std::fs::create_dir(&profiles).unwrap();
+        std::fs::write(
+            profiles.join("Work.profile"),
+            "[Appearance]\nColorScheme=Breeze\nFont=Hack,11\n\n[General]\nName=Work\n",
+        )
+        .unwrap();
+        std::fs::write(profiles.join("Shell.profile"), "[General]\nName=Shell\n").unwrap();
+        let konsolerc = dir.path().join("konsolerc");
+        std::fs::write(&konsolerc, "[Desktop Entry]\nDefaultProfile=Work.profile\n").unwrap();
+
+        let adapter = KonsoleAdapter::with_path(profiles.clone()).with_konsolerc(konsolerc);
+        assert_eq!(adapter.get_theme().await.unwrap(), "Breeze");
+
+        adapter.set_theme("Solarized").await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(profiles.join("Work.profile")).unwrap(),
+            "[Appearance]\nColorScheme=Solarized\nFont=Hack,11\n\n[General]\nName=Work\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(profiles.join("Shell.profile")).unwrap(),
+            "[General]\nName=Shell\n\n[Appearance]\nColorScheme=Solarized\n"
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "Solarized");
+
+        let empty = KonsoleAdapter::with_path(dir.path().join("missing"));
+        assert!(empty.set_theme("Solarized").await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_gnome_terminal_writes_profile_through_dconf() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        std::fs::create_dir(&themes).unwrap();
+        std::fs::write(
+            themes.join("nord.dconf"),
+            "[/]\nbackground-color='#2E3440'\npalette=['#3B4252', '#BF616A']\n",
+        )
+        .unwrap();
+
+        // A stub dconf keeping each key in a file under db/
+        let bin = dir.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let stub = bin.join("dconf");
+        std::fs::write(
+            &stub,
+            format!(
+                "#!/bin/sh\nPATH=/usr/bin:/bin\ndb='{}'\ncase \"$1\" in\n  read) cat \"$db$2\" 2>/dev/null; exit 0 ;;\n  write) mkdir -p \"$(dirname \"$db$2\")\" && printf '%s\\n' \"$3\" > \"$db$2\" ;;\n  dump) echo '[/]'; for f in \"$db$2\"*; do [ -f \"$f\" ] && echo \"${{f##*/}}=$(cat \"$f\")\"; done; exit 0 ;;\n  reset) rm -rf \"$db$3\" ;;\n  *) exit 1 ;;\nesac\n",
+                dir.path().join("db").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let profiles = dir.path().join("db/org/gnome/terminal/legacy/profiles:");
+        std::fs::create_dir_all(&profiles).unwrap();
+        std::fs::write(profiles.join("list"), "['0a1b', 'c2d3']\n").unwrap();
+
+        let adapter = GnomeTerminalAdapter::new()
+            .with_themes_dir(themes)
+            .with_runner(std::sync::Arc::new(SystemRunner::with_search_path(&bin)));
+        assert_eq!(adapter.get_theme().await.unwrap(), "system");
+
+        // Without a default profile, the first listed one is used
+        adapter.set_theme("nord").await.unwrap();
+        let profile = profiles.join(":0a1b");
+        assert_eq!(
+            std::fs::read_to_string(profile.join("palette")).unwrap(),
+            "['#3B4252', '#BF616A']\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(profile.join("use-theme-colors")).unwrap(),
+            "false\n"
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "nord");
+
+        // Rolling back restores the profile keys themselves, not a theme name
+        let state = adapter.capture_state().await.unwrap().unwrap();
+        adapter.set_theme("system").await.unwrap();
+        assert_eq!(adapter.get_theme().await.unwrap(), "system");
+        adapter.restore_state(&state).await.unwrap();
+        assert_eq!(adapter.get_theme().await.unwrap(), "nord");
+        assert!(adapter.set_theme("missing").await.is_err());
+
+        // The default themes are built in and need no theme file
+        let defaults = adapter.default_themes();
+        adapter.set_theme(&defaults["light"]).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(profile.join("background-color")).unwrap(),
+            "'#EEEEEC'\n"
+        );
+        assert_eq!(adapter.get_theme().await.unwrap(), "tango-light");
+        adapter.set_theme(&defaults["dark"]).await.unwrap();
+        assert_eq!(adapter.get_theme().await.unwrap(), "tango-dark");
+
+        // Without dconf the profile follows the desktop colors
+        let no_dconf = GnomeTerminalAdapter::new().with_runner(std::sync::Arc::new(
+            SystemRunner::with_search_path(dir.path().join("empty")),
+        ));
+        assert_eq!(no_dconf.get_theme().await.unwrap(), "system");
     }
 }
@@ -0,0 +1,39 @@
+//! Helpers for the small Lua modules themesync generates for Lua-configured apps.
+//!
+//! Apps like WezTerm and Neovim are configured in Lua, which themesync never edits.
+//! Adapters instead write a module of their own that the user requires once.
+
+/// Returns `value` as a double-quoted Lua string literal
+pub fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Parses the quoted string literal at the start of `code`, e.g. `"Tokyo Night")`
+///
+/// Only the escapes `quote` produces are understood; returns `None` if `code` does not
+/// start with a complete single- or double-quoted literal.
+pub fn unquote(code: &str) -> Option<String> {
+    let code = code.trim_start();
+    let quote = code.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let mut chars = code[1..].chars();
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            c if c == quote => return Some(value),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Whether `config` calls `require` on `module` outside of a comment
+///
+/// Also matches Vimscript's `lua require("module")`.
+pub fn requires_module(config: &str, module: &str) -> bool {
+    let quoted = [format!("\"{}\"", module), format!("'{}'", module)];
+    config.lines().any(|line| {
+        let code = line.split("--").next().unwrap_or_default();
+        code.contains("require") && quoted.iter().any(|name| code.contains(name.as_str()))
+    })
+}
@@ -1,13 +1,13 @@
 use anyhow::Result;
 use clap::Parser;
 
-mod adapter;
-mod adapters;
 mod cli;
 mod commands;
-mod config;
 
-use cli::{AppCommands, Cli, Commands, ThemeCommands};
+// Library modules, re-exported at the crate root so commands can use `crate::` paths
+use themesync::{adapter, adapters, apply, backup, config};
+
+use cli::{AppCommands, BackupCommands, Cli, Commands, ThemeCommands};
 use config::Config;
 
 async fn run_cli() -> Result<()> {
@@ -16,8 +16,8 @@ async fn run_cli() -> Result<()> {
     let mut config = Config::load_from_file(&config_path)?;
 
     match &cli.command {
-        Some(Commands::Set { theme }) => {
-            commands::set_theme(&mut config, &config_path, theme).await?;
+        Some(Commands::Set { theme, atomic }) => {
+            commands::set_theme(&mut config, &config_path, theme, *atomic).await?;
         }
         Some(Commands::Toggle) => {
             commands::toggle_theme(&mut config, &config_path).await?;
@@ -25,12 +25,21 @@ async fn run_cli() -> Result<()> {
         Some(Commands::Status) => {
             commands::show_status(&config).await?;
         }
+        Some(Commands::Doctor) => {
+            commands::run_doctor(&config).await?;
+        }
         Some(Commands::Themes(ThemeCommands::List)) => {
             commands::list_themes(&config)?;
         }
         Some(Commands::Apps(AppCommands::List)) => {
             commands::list_apps(&config).await?;
         }
+        Some(Commands::Backup(BackupCommands::List)) => {
+            commands::list_backups(&config)?;
+        }
+        Some(Commands::Backup(BackupCommands::Restore { id, app })) => {
+            commands::restore_backup(&config, id, app.as_deref())?;
+        }
         None => {
             println!("Use --help for usage information");
         }
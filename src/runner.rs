@@ -0,0 +1,72 @@
+use std::ffi::OsString;
+use std::io;
+use tokio::process::Command;
+
+/// Captured result of running an external program
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs external programs on behalf of adapters
+///
+/// Adapters that drive an app's CLI (`kitty @`, `tmux`, `emacsclient`, ...) take a
+/// runner so tests can substitute a fake that records calls instead of spawning them.
+#[async_trait::async_trait]
+pub trait CommandRunner: Send + Sync {
+    async fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+}
+
+/// Runs programs as real child processes
+#[derive(Debug, Clone, Default)]
+pub struct SystemRunner {
+    search_path: Option<OsString>,
+}
+
+impl SystemRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks programs up in `search_path` (a `PATH`-style list) instead of the inherited `PATH`
+    pub fn with_search_path(search_path: impl Into<OsString>) -> Self {
+        Self {
+            search_path: Some(search_path.into()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CommandRunner for SystemRunner {
+    async fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let mut command = Command::new(program);
+        command.args(args).kill_on_drop(true);
+        // Setting PATH on the command also makes the program lookup use it
+        if let Some(path) = &self.search_path {
+            command.env("PATH", path);
+        }
+
+        let output = command.output().await?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// Whether `path` is a Unix domain socket, used to discover running app instances
+pub fn is_socket(path: &std::path::Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
@@ -0,0 +1,369 @@
+//! Lossless attribute edits of small XML config files such as JetBrains' `options/*.xml`.
+//!
+//! Elements are addressed by a path of selectors from the root element down, e.g.
+//! `application > component[name=LafManager] > laf`. `set_attribute` rewrites only the
+//! changed attribute value, or inserts the missing elements, leaving comments,
+//! attribute order and indentation elsewhere untouched.
+
+use std::ops::Range;
+
+/// Matches an element by name and, optionally, by the value of one attribute
+#[derive(Debug, Clone, Copy)]
+pub struct Selector<'a> {
+    pub name: &'a str,
+    pub attribute: Option<(&'a str, &'a str)>,
+}
+
+impl<'a> Selector<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            attribute: None,
+        }
+    }
+
+    /// Matches only elements whose attribute `name` equals `value`
+    pub fn with_attribute(self, name: &'a str, value: &'a str) -> Self {
+        Self {
+            attribute: Some((name, value)),
+            ..self
+        }
+    }
+
+    fn matches(&self, src: &str, tag: &Tag) -> bool {
+        if tag.name != self.name {
+            return false;
+        }
+        match self.attribute {
+            Some((name, value)) => {
+                attribute_in_tag(&src[tag.range.clone()], name).as_deref() == Some(value)
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    Open,
+    Close,
+    Empty,
+}
+
+#[derive(Debug)]
+struct Tag<'a> {
+    kind: TagKind,
+    name: &'a str,
+    range: Range<usize>,
+}
+
+/// Returns the element tags of `src` in document order, skipping comments, CDATA
+/// sections, processing instructions and declarations
+fn tags(src: &str) -> Vec<Tag<'_>> {
+    let mut tags = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = src[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &src[start..];
+        let skip_to = |terminator: &str| {
+            rest.find(terminator)
+                .map_or(src.len(), |end| start + end + terminator.len())
+        };
+
+        if rest.starts_with("<!--") {
+            pos = skip_to("-->");
+            continue;
+        }
+        // CDATA may hold `<` and `>` and ends only at `]]>`
+        if rest.starts_with("<![CDATA[") {
+            pos = skip_to("]]>");
+            continue;
+        }
+        if rest.starts_with("<?") {
+            pos = skip_to("?>");
+            continue;
+        }
+        if rest.starts_with("<!") {
+            pos = skip_to(">");
+            continue;
+        }
+
+        // Find the closing `>`, ignoring any inside quoted attribute values
+        let mut quote = None;
+        let Some(end) = rest.char_indices().skip(1).find_map(|(i, c)| {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (None, '>') => return Some(start + i + 1),
+                _ => {}
+            }
+            None
+        }) else {
+            break;
+        };
+
+        let text = &src[start..end];
+        let (kind, name_start) = if text.starts_with("</") {
+            (TagKind::Close, 2)
+        } else if text.ends_with("/>") {
+            (TagKind::Empty, 1)
+        } else {
+            (TagKind::Open, 1)
+        };
+        let name = text[name_start..]
+            .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .next()
+            .unwrap_or_default();
+
+        tags.push(Tag {
+            kind,
+            name,
+            range: start..end,
+        });
+        pos = end;
+    }
+
+    tags
+}
+
+/// Returns the index of the tag closing the open tag at `open`
+fn matching_close(tags: &[Tag], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, tag) in tags.iter().enumerate().skip(open + 1) {
+        match tag.kind {
+            TagKind::Open => depth += 1,
+            TagKind::Close if depth == 0 => return Some(index),
+            TagKind::Close => depth -= 1,
+            TagKind::Empty => {}
+        }
+    }
+    None
+}
+
+/// Returns the byte ranges of each attribute's name and raw value in `tag`
+fn attribute_spans(tag: &str) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut spans = Vec::new();
+    let bytes = tag.as_bytes();
+    // Skip `<` and the element name
+    let mut pos = tag
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(tag.len());
+
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let name_start = pos;
+        while pos < bytes.len() && !b"= \t\r\n/>".contains(&bytes[pos]) {
+            pos += 1;
+        }
+        if pos == name_start {
+            return spans;
+        }
+        let name = name_start..pos;
+
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if bytes.get(pos) != Some(&b'=') {
+            return spans;
+        }
+        pos += 1;
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+
+        let Some(&quote) = bytes.get(pos).filter(|b| **b == b'"' || **b == b'\'') else {
+            return spans;
+        };
+        let Some(len) = tag[pos + 1..].find(quote as char) else {
+            return spans;
+        };
+        spans.push((name, pos + 1..pos + 1 + len));
+        pos += len + 2;
+    }
+}
+
+fn attribute_in_tag(tag: &str, name: &str) -> Option<String> {
+    attribute_spans(tag)
+        .into_iter()
+        .find(|(span, _)| &tag[span.clone()] == name)
+        .map(|(_, value)| unescape(&tag[value]))
+}
+
+/// Escapes `value` for use inside a double-quoted attribute
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Resolves the predefined entities in an attribute value
+pub fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Finds the tag index of the element at `path`, or how far the path got
+///
+/// Returns `Ok(index)` if the whole path matched, else `Err((depth, parent))` where
+/// `depth` selectors matched and `parent` is the tag index of the deepest match.
+fn locate(src: &str, tags: &[Tag], path: &[Selector]) -> Result<usize, (usize, Option<usize>)> {
+    let mut range = 0..tags.len();
+    let mut parent = None;
+
+    for (depth, selector) in path.iter().enumerate() {
+        let found = range.clone().find(|&index| {
+            tags[index].kind != TagKind::Close && selector.matches(src, &tags[index])
+        });
+        let Some(index) = found else {
+            return Err((depth, parent));
+        };
+        if depth + 1 == path.len() {
+            return Ok(index);
+        }
+
+        parent = Some(index);
+        range = match tags[index].kind {
+            TagKind::Open => match matching_close(tags, index) {
+                Some(close) => index + 1..close,
+                None => return Err((depth + 1, parent)),
+            },
+            _ => index..index,
+        };
+    }
+
+    Err((0, None))
+}
+
+/// Returns the value of `attribute` on the first element at `path`
+pub fn get_attribute(src: &str, path: &[Selector], attribute: &str) -> Option<String> {
+    let tags = tags(src);
+    let index = locate(src, &tags, path).ok()?;
+    attribute_in_tag(&src[tags[index].range.clone()], attribute)
+}
+
+/// Renders `path` as nested elements, the last carrying `attribute="value"`
+fn render(path: &[Selector], attribute: &str, value: &str, indent: &str) -> String {
+    let Some((selector, rest)) = path.split_first() else {
+        return String::new();
+    };
+
+    let mut open = format!("{}<{}", indent, selector.name);
+    if let Some((name, value)) = selector.attribute {
+        open.push_str(&format!(" {}=\"{}\"", name, escape(value)));
+    }
+    if rest.is_empty() {
+        return format!("{} {}=\"{}\" />\n", open, attribute, escape(value));
+    }
+
+    format!(
+        "{}>\n{}{}</{}>\n",
+        open,
+        render(rest, attribute, value, &format!("{}  ", indent)),
+        indent,
+        selector.name
+    )
+}
+
+/// Returns the whitespace at the start of the line containing `pos`
+fn line_indent(src: &str, pos: usize) -> &str {
+    let line_start = src[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let line = &src[line_start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Sets `attribute` on the first element at `path` to `value`
+///
+/// Missing elements along the path are created, indented two spaces per level. Only
+/// the changed attribute value, or the inserted elements, differ from `src`.
+pub fn set_attribute(src: &str, path: &[Selector], attribute: &str, value: &str) -> String {
+    let tags = tags(src);
+
+    match locate(src, &tags, path) {
+        Ok(index) => {
+            let range = tags[index].range.clone();
+            let tag = &src[range.clone()];
+            let existing = attribute_spans(tag)
+                .into_iter()
+                .find(|(name, _)| &tag[name.clone()] == attribute);
+
+            let (at, text) = match existing {
+                Some((_, value_span)) => (
+                    range.start + value_span.start..range.start + value_span.end,
+                    escape(value),
+                ),
+                None => {
+                    let body_end = tag
+                        .trim_end_matches('>')
+                        .trim_end_matches('/')
+                        .trim_end()
+                        .len();
+                    let at = range.start + body_end;
+                    (at..at, format!(" {}=\"{}\"", attribute, escape(value)))
+                }
+            };
+            format!("{}{}{}", &src[..at.start], text, &src[at.end..])
+        }
+        Err((depth, None)) => {
+            // No root element yet; append one
+            let separator = if src.is_empty() || src.ends_with('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            format!(
+                "{}{}{}",
+                src,
+                separator,
+                render(&path[depth..], attribute, value, "")
+            )
+        }
+        Err((depth, Some(parent))) => {
+            let parent_tag = &tags[parent];
+            let parent_indent = line_indent(src, parent_tag.range.start);
+            let child_indent = format!("{}  ", parent_indent);
+            let children = render(&path[depth..], attribute, value, &child_indent);
+
+            if parent_tag.kind == TagKind::Empty {
+                // Expand `<parent ... />` into an element holding the new children
+                let tag = &src[parent_tag.range.clone()];
+                let open = tag.trim_end_matches('>').trim_end_matches('/').trim_end();
+                return format!(
+                    "{}{}>\n{}{}</{}>{}",
+                    &src[..parent_tag.range.start],
+                    open,
+                    children,
+                    parent_indent,
+                    parent_tag.name,
+                    &src[parent_tag.range.end..]
+                );
+            }
+
+            let close = match matching_close(&tags, parent) {
+                Some(close) => tags[close].range.start,
+                None => src.len(),
+            };
+            let line_start = src[..close].rfind('\n').map_or(0, |i| i + 1);
+            if src[line_start..close].trim().is_empty() {
+                format!("{}{}{}", &src[..line_start], children, &src[line_start..])
+            } else {
+                // The closing tag shares a line with other content
+                format!(
+                    "{}\n{}{}{}",
+                    &src[..close],
+                    children,
+                    parent_indent,
+                    &src[close..]
+                )
+            }
+        }
+    }
+}